@@ -55,10 +55,10 @@ unsafe extern "system" fn window_procedure(
     wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
-    let _w = DroplessWindow::new(hwnd);
+    let w = DroplessWindow::new(hwnd);
 
     match msg {
-        winuser::WM_CLOSE => { winuser::DestroyWindow(hwnd); },
+        winuser::WM_CLOSE => { let _ = w.destroy(); },
         winuser::WM_DESTROY => winuser::PostQuitMessage(0),
         _ => return winuser::DefWindowProcA(hwnd, msg, wparam, lparam),
     }
@@ -66,7 +66,7 @@ unsafe extern "system" fn window_procedure(
     0
 }
 
-fn main() -> porcupine::Result<()> {
+fn run() -> porcupine::Result<i32> {
     // register the window class
     let wc_name = "PorcupineBasicsTest".to_string();
     let mut wc = OwnedWindowClass::new(wc_name);
@@ -85,11 +85,11 @@ fn main() -> porcupine::Result<()> {
     w.show(CmdShow::Show);
     w.update()?;
 
-    // create the event loop
-    while let Some(ref msg) = porcupine::get_message()? {
-        porcupine::translate_message(msg);
-        porcupine::dispatch_message(msg);
-    }
+    // run the event loop
+    porcupine::run_event_loop(|_| {})
+}
 
-    Ok(())
+fn main() -> porcupine::Result<()> {
+    let exit_code = run()?;
+    std::process::exit(exit_code)
 }
@@ -43,16 +43,35 @@
  * ----------------------------------------------------------------------------------
  */
 
-use crate::{mutexes::Mutex, DeviceContext};
-use alloc::sync::{Arc, Weak};
-use core::{mem, ptr::NonNull, sync::atomic::AtomicPtr};
+#[cfg(feature = "std")]
+use crate::WString;
+use crate::{mutexes::Mutex, CopyOperation, DeviceContext};
+use alloc::{
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
+use core::{
+    mem,
+    ptr::{self, NonNull},
+    slice,
+    sync::atomic::AtomicPtr,
+};
 use cty::{c_int, c_long};
-use euclid::default::Size2D;
+use euclid::default::{Point2D, Rect, Size2D};
 use maybe_uninit::MaybeUninit;
 use winapi::{
     ctypes::c_void,
-    shared::{minwindef::BYTE, windef::HBITMAP__},
-    um::wingdi::{self, BITMAP},
+    shared::{
+        minwindef::{BYTE, DWORD, UINT, WORD},
+        windef::{HBITMAP, HBITMAP__},
+    },
+    um::wingdi::{self, BITMAP, BITMAPINFO, BITMAPINFOHEADER},
+};
+#[cfg(feature = "std")]
+use {
+    std::{fs::File, io::Write, path::Path},
+    winapi::um::{wingdi::BITMAPFILEHEADER, winuser},
 };
 
 static OWNING_DC_NONE: &'static str = "Owning DC was not properly set";
@@ -62,6 +81,7 @@ pub struct Bitmap {
     hbitmap: Arc<Mutex<AtomicPtr<HBITMAP__>>>,
     owning_dc: Option<DeviceContext>, // Option only for initialization. This field should never be None
     bm: BITMAP,
+    dib_bits: *mut u32, // null unless this is a DIB section
 }
 
 impl Drop for Bitmap {
@@ -69,23 +89,61 @@ impl Drop for Bitmap {
         // drop the owning dc before anything else
         mem::drop(self.owning_dc.take().expect(OWNING_DC_NONE));
 
-        unsafe { wingdi::DeleteObject(*self.hbitmap.lock().get_mut() as *mut c_void) };
+        unsafe {
+            crate::delete_gdi_object(*self.hbitmap.lock().get_mut() as *mut c_void, "bitmap")
+        };
     }
 }
 
+// view a plain-old-data struct as raw bytes
+#[cfg(feature = "std")]
+#[inline]
+unsafe fn struct_bytes<T>(t: &T) -> &[u8] {
+    slice::from_raw_parts(t as *const T as *const u8, mem::size_of::<T>())
+}
+
 impl Bitmap {
     /// Create a new bitmap from size and raw data. Data is expected to be raw RGB bytes.
+    #[inline]
     pub fn from_dc_and_data(
         dc: &DeviceContext,
         size: Size2D<c_int>,
         data: &[BYTE],
+    ) -> crate::Result<Self> {
+        Self::from_dc_and_bits(dc, size, 24, data)
+    }
+
+    /// Create a new 32-bit bitmap with an alpha channel from size and raw data. Data is
+    /// expected to be four bytes per pixel: the color bytes, laid out the same way as in
+    /// `from_dc_and_data`, followed by the alpha byte.
+    #[inline]
+    pub fn from_dc_and_rgba(
+        dc: &DeviceContext,
+        size: Size2D<c_int>,
+        data: &[BYTE],
+    ) -> crate::Result<Self> {
+        if data.len() != size.width as usize * size.height as usize * 4 {
+            return Err(crate::Error::StaticMsg(
+                "RGBA data length does not match the bitmap's size",
+            ));
+        }
+
+        Self::from_dc_and_bits(dc, size, 32, data)
+    }
+
+    // create a bitmap from raw data with the given bit count
+    fn from_dc_and_bits(
+        dc: &DeviceContext,
+        size: Size2D<c_int>,
+        bit_count: UINT,
+        data: &[BYTE],
     ) -> crate::Result<Self> {
         let hbitmap = unsafe {
             wingdi::CreateBitmap(
                 size.width,
                 size.height,
                 1,
-                24,
+                bit_count,
                 data.as_ptr() as *const c_void,
             )
         };
@@ -93,35 +151,142 @@ impl Bitmap {
         if hbitmap.is_null() {
             Err(crate::win32_error(crate::Win32Function::CreateBitmap))
         } else {
-            // basic bm
-            let mut bm: MaybeUninit<BITMAP> = MaybeUninit::zeroed();
-            if unsafe {
-                wingdi::GetObjectA(
-                    hbitmap as *mut c_void,
-                    mem::size_of::<BITMAP>() as c_int,
-                    bm.as_mut_ptr() as *mut c_void,
-                )
-            } == 0
-            {
-                return Err(crate::win32_error(crate::Win32Function::GetObjectA));
-            }
+            Self::from_hbitmap(dc, hbitmap)
+        }
+    }
 
-            let mut b = Self {
-                hbitmap: Arc::new(Mutex::new(AtomicPtr::new(hbitmap))),
-                owning_dc: None,
-                bm: unsafe { bm.assume_init() },
-            };
+    /// Load a bitmap from a `.bmp` file on disk.
+    #[cfg(feature = "std")]
+    pub fn from_file(dc: &DeviceContext, path: &Path) -> crate::Result<Self> {
+        let path = WString::from_os_str(path.as_os_str());
+        let hbitmap = unsafe {
+            winuser::LoadImageW(
+                ptr::null_mut(),
+                path.as_ptr(),
+                winuser::IMAGE_BITMAP,
+                0,
+                0,
+                winuser::LR_LOADFROMFILE,
+            )
+        };
+
+        if hbitmap.is_null() {
+            Err(crate::win32_error(crate::Win32Function::LoadImage))
+        } else {
+            Self::from_hbitmap(dc, hbitmap as HBITMAP)
+        }
+    }
 
-            // set up a DC for drawing
-            let mut owning_dc = dc.create_compatible()?; // TODO: this might cause a panic
-            owning_dc.set_bitmap(&b)?; // TODO: same here
+    /// Create a new 32-bit DIB section, whose pixels can be accessed directly through
+    /// `dib_pixels` and `dib_pixels_mut`.
+    ///
+    /// The pixel memory is laid out top-down, one `u32` per pixel, with rows following
+    /// each other without padding. Each pixel is stored in BGRA order, which means that
+    /// when read as a `u32` on a little-endian machine it takes the form `0xAARRGGBB`.
+    pub fn dib_section(dc: &DeviceContext, size: Size2D<c_int>) -> crate::Result<Self> {
+        let mut bmi: BITMAPINFO = unsafe { mem::zeroed() };
+        bmi.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as DWORD;
+        bmi.bmiHeader.biWidth = size.width;
+        bmi.bmiHeader.biHeight = -size.height; // negative height means top-down
+        bmi.bmiHeader.biPlanes = 1;
+        bmi.bmiHeader.biBitCount = 32;
+        bmi.bmiHeader.biCompression = wingdi::BI_RGB;
 
-            b.owning_dc = Some(owning_dc);
+        let mut bits: *mut c_void = ptr::null_mut();
+        let hbitmap = unsafe {
+            wingdi::CreateDIBSection(
+                dc.hdc().as_mut(),
+                &bmi,
+                wingdi::DIB_RGB_COLORS,
+                &mut bits,
+                ptr::null_mut(),
+                0,
+            )
+        };
 
+        if hbitmap.is_null() || bits.is_null() {
+            Err(crate::win32_error(crate::Win32Function::CreateDIBSection))
+        } else {
+            let mut b = Self::from_hbitmap(dc, hbitmap)?;
+            b.dib_bits = bits as *mut u32;
             Ok(b)
         }
     }
 
+    /// Create a new 32-bit DIB section from straight (non-premultiplied) RGBA data, four
+    /// bytes per pixel, in top-down order.
+    ///
+    /// `AlphaBlend` and `UpdateLayeredWindow` expect each color channel to already be
+    /// multiplied by its pixel's alpha. Passing straight alpha data to them produces
+    /// dark halos around translucent edges. This constructor premultiplies the data
+    /// while copying it, so the result can be used with them directly. Unlike
+    /// `from_dc_and_rgba`, which passes its data through as-is, the input here is in
+    /// plain red, green, blue, alpha order.
+    pub fn from_rgba_straight(
+        dc: &DeviceContext,
+        size: Size2D<c_int>,
+        rgba: &[BYTE],
+    ) -> crate::Result<Self> {
+        if rgba.len() != size.width as usize * size.height as usize * 4 {
+            return Err(crate::Error::StaticMsg(
+                "RGBA data length does not match the bitmap's size",
+            ));
+        }
+
+        let mut b = Self::dib_section(dc, size)?;
+        let pixels = b
+            .dib_pixels_mut()
+            .ok_or(crate::Error::StaticMsg("DIB section has no pixel data"))?;
+
+        #[inline]
+        fn premultiply(c: BYTE, a: BYTE) -> u32 {
+            (u32::from(c) * u32::from(a) + 127) / 255
+        }
+
+        for (pixel, src) in pixels.iter_mut().zip(rgba.chunks_exact(4)) {
+            let a = src[3];
+            *pixel = (u32::from(a) << 24)
+                | (premultiply(src[0], a) << 16)
+                | (premultiply(src[1], a) << 8)
+                | premultiply(src[2], a);
+        }
+
+        Ok(b)
+    }
+
+    // take ownership of a bitmap handle and set up its owning DC
+    pub(crate) fn from_hbitmap(dc: &DeviceContext, hbitmap: HBITMAP) -> crate::Result<Self> {
+        // basic bm
+        let mut bm: MaybeUninit<BITMAP> = MaybeUninit::zeroed();
+        if unsafe {
+            wingdi::GetObjectW(
+                hbitmap as *mut c_void,
+                mem::size_of::<BITMAP>() as c_int,
+                bm.as_mut_ptr() as *mut c_void,
+            )
+        } == 0
+        {
+            let err = crate::win32_error(crate::Win32Function::GetObjectW);
+            unsafe { wingdi::DeleteObject(hbitmap as *mut c_void) };
+            return Err(err);
+        }
+
+        let mut b = Self {
+            hbitmap: Arc::new(Mutex::new(AtomicPtr::new(hbitmap))),
+            owning_dc: None,
+            bm: unsafe { bm.assume_init() },
+            dib_bits: ptr::null_mut(),
+        };
+
+        // set up a DC for drawing
+        let mut owning_dc = dc.create_compatible()?; // TODO: this might cause a panic
+        owning_dc.set_bitmap(&b)?; // TODO: same here
+
+        b.owning_dc = Some(owning_dc);
+
+        Ok(b)
+    }
+
     /// Get the handle to a bitmap.
     ///
     /// # Safety
@@ -148,6 +313,140 @@ impl Bitmap {
         self.bm.bmHeight
     }
 
+    /// Get the size of the internal image. Unlike `height`, this is never negative, even
+    /// for top-down DIB sections.
+    #[inline]
+    pub fn size(&self) -> Size2D<c_int> {
+        Size2D::new(self.bm.bmWidth, self.bm.bmHeight.abs())
+    }
+
+    // the number of pixels in a DIB section
+    #[inline]
+    fn dib_len(&self) -> usize {
+        let size = self.size();
+        (size.width as usize) * (size.height as usize)
+    }
+
+    /// Get the pixels of this bitmap, if it is a DIB section. See `dib_section` for the
+    /// memory layout.
+    ///
+    /// GDI may batch drawing operations, so make sure they have been flushed before
+    /// reading pixels that were drawn through the owning DC.
+    #[inline]
+    pub fn dib_pixels(&self) -> Option<&[u32]> {
+        if self.dib_bits.is_null() {
+            None
+        } else {
+            Some(unsafe { slice::from_raw_parts(self.dib_bits, self.dib_len()) })
+        }
+    }
+
+    /// Get the pixels of this bitmap mutably, if it is a DIB section. See `dib_section`
+    /// for the memory layout.
+    #[inline]
+    pub fn dib_pixels_mut(&mut self) -> Option<&mut [u32]> {
+        if self.dib_bits.is_null() {
+            None
+        } else {
+            Some(unsafe { slice::from_raw_parts_mut(self.dib_bits, self.dib_len()) })
+        }
+    }
+
+    // read the pixels of this bitmap out, using the given bit count
+    fn read_bits(
+        &self,
+        bit_count: WORD,
+        top_down: bool,
+    ) -> crate::Result<(BITMAPINFOHEADER, Vec<u8>)> {
+        let Size2D { width, height, .. } = self.size();
+        // rows are padded to 4-byte boundaries; div_ceil is too new for older compilers
+        #[allow(clippy::manual_div_ceil)]
+        let stride = (width as usize * bit_count as usize + 31) / 32 * 4;
+
+        let mut bmi: BITMAPINFO = unsafe { mem::zeroed() };
+        bmi.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as DWORD;
+        bmi.bmiHeader.biWidth = width;
+        bmi.bmiHeader.biHeight = if top_down { -height } else { height };
+        bmi.bmiHeader.biPlanes = 1;
+        bmi.bmiHeader.biBitCount = bit_count;
+        bmi.bmiHeader.biCompression = wingdi::BI_RGB;
+        bmi.bmiHeader.biSizeImage = (stride * height as usize) as DWORD;
+
+        let mut data = vec![0; stride * height as usize];
+        let dc = self.dc();
+        // GetDIBits requires that the bitmap isn't selected into a DC
+        let lines = dc.without_storage(|| unsafe {
+            wingdi::GetDIBits(
+                dc.hdc().as_mut(),
+                self.hbitmap().as_mut(),
+                0,
+                height as UINT,
+                data.as_mut_ptr() as *mut c_void,
+                &mut bmi,
+                wingdi::DIB_RGB_COLORS,
+            )
+        });
+
+        if lines == 0 {
+            Err(crate::win32_error(crate::Win32Function::GetDIBits))
+        } else {
+            Ok((bmi.bmiHeader, data))
+        }
+    }
+
+    /// Copy this whole bitmap onto another device context, with its top left corner at
+    /// `dest_pt`.
+    #[inline]
+    pub fn blit_to(&self, dest: &DeviceContext, dest_pt: Point2D<c_int>) -> crate::Result<()> {
+        dest.copy_from(
+            self.dc(),
+            Rect::new(Point2D::zero(), self.size()),
+            dest_pt,
+            CopyOperation::SrcCopy,
+        )
+    }
+
+    /// Read the pixels of this bitmap out. The result has four bytes per pixel, in the
+    /// order red, green, blue, alpha, with rows going from top to bottom and no padding
+    /// between them. For bitmaps that are not 32-bit, the alpha is always 255.
+    pub fn pixels(&self) -> crate::Result<Vec<u8>> {
+        let (_, mut data) = self.read_bits(32, true)?;
+        let has_alpha = self.bm.bmBitsPixel == 32;
+
+        // GDI stores pixels as blue, green, red, alpha
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+            if !has_alpha {
+                pixel[3] = 0xFF;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Save this bitmap to a `.bmp` file. 32-bit bitmaps are saved as 32-bit images;
+    /// everything else is saved as a 24-bit image.
+    #[cfg(feature = "std")]
+    pub fn save_to_bmp(&self, path: &Path) -> crate::Result<()> {
+        let bit_count = if self.bm.bmBitsPixel == 32 { 32 } else { 24 };
+        let (info_header, data) = self.read_bits(bit_count, false)?;
+
+        let offset = mem::size_of::<BITMAPFILEHEADER>() + mem::size_of::<BITMAPINFOHEADER>();
+        let file_header = BITMAPFILEHEADER {
+            bfType: 0x4D42, // "BM"
+            bfSize: (offset + data.len()) as DWORD,
+            bfReserved1: 0,
+            bfReserved2: 0,
+            bfOffBits: offset as DWORD,
+        };
+
+        let mut file = File::create(path)?;
+        file.write_all(unsafe { struct_bytes(&file_header) })?;
+        file.write_all(unsafe { struct_bytes(&info_header) })?;
+        file.write_all(&data)?;
+        Ok(())
+    }
+
     /// Get a weak reference to this bitmap.
     pub fn weak_reference(&self) -> Weak<Mutex<AtomicPtr<HBITMAP__>>> {
         Arc::downgrade(&self.hbitmap)
@@ -0,0 +1,106 @@
+/* -----------------------------------------------------------------------------------
+ * src/buffered.rs - Double-buffered painting
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::{Bitmap, DeviceContext, GenericWindow};
+use core::cmp;
+use euclid::default::Point2D;
+
+/// A double-buffered paint operation. Drawing goes to an off-screen bitmap the size of
+/// the window's client area, which is copied onto the window all at once when the
+/// painter is presented or dropped. This avoids the flicker of drawing complex scenes
+/// directly onto the screen.
+///
+/// Create one in response to `WM_PAINT`, in place of `DeviceContext::begin_paint`. The
+/// buffer is sized to the client area every time, so it keeps up with resizes. Its
+/// contents start out undefined, so the whole client area should be drawn.
+pub struct BufferedPaint {
+    buffer: Bitmap,
+    window_dc: DeviceContext,
+    presented: bool,
+}
+
+impl BufferedPaint {
+    /// Begin a buffered paint on a window.
+    pub fn new<T: GenericWindow + ?Sized>(window: &T) -> crate::Result<Self> {
+        let size = window.client_rect()?.size;
+        let window_dc = DeviceContext::begin_paint(window)?;
+
+        // CreateCompatibleBitmap returns a monochrome bitmap for an empty size
+        let buffer = window_dc
+            .create_compatible_bitmap((cmp::max(size.width, 1), cmp::max(size.height, 1)).into())?;
+
+        Ok(Self {
+            buffer,
+            window_dc,
+            presented: false,
+        })
+    }
+
+    /// Get the device context to draw on.
+    #[inline]
+    pub fn dc(&self) -> &DeviceContext {
+        self.buffer.dc()
+    }
+
+    /// Copy the buffer onto the window and end the paint operation. Dropping the painter
+    /// does the same, but ignores any errors.
+    #[inline]
+    pub fn present(mut self) -> crate::Result<()> {
+        self.presented = true;
+        self.blit()
+    }
+
+    fn blit(&self) -> crate::Result<()> {
+        self.buffer.blit_to(&self.window_dc, Point2D::zero())
+    }
+}
+
+impl Drop for BufferedPaint {
+    fn drop(&mut self) {
+        if !self.presented {
+            let _ = self.blit();
+        }
+    }
+}
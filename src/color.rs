@@ -0,0 +1,132 @@
+/* -----------------------------------------------------------------------------------
+ * src/color.rs - A simple RGB color type.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use winapi::{
+    shared::windef::COLORREF,
+    um::{
+        wingdi::{GetBValue, GetGValue, GetRValue, RGB},
+        winuser,
+    },
+};
+
+/// A color, made up of red, green and blue components.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    /// Create a new color from its red, green and blue components.
+    #[inline]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Convert this color into a Win32 `COLORREF`.
+    #[inline]
+    pub fn to_colorref(&self) -> COLORREF {
+        RGB(self.r, self.g, self.b)
+    }
+
+    /// Convert a Win32 `COLORREF` into a color.
+    #[inline]
+    pub fn from_colorref(c: COLORREF) -> Self {
+        Self::rgb(GetRValue(c), GetGValue(c), GetBValue(c))
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    #[inline]
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+/// The colors that make up the system's theme, as used for window backgrounds, button faces
+/// and highlighted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum SysColor {
+    ScrollBar = winuser::COLOR_SCROLLBAR,
+    Background = winuser::COLOR_BACKGROUND,
+    ActiveCaption = winuser::COLOR_ACTIVECAPTION,
+    InactiveCaption = winuser::COLOR_INACTIVECAPTION,
+    Menu = winuser::COLOR_MENU,
+    Window = winuser::COLOR_WINDOW,
+    WindowFrame = winuser::COLOR_WINDOWFRAME,
+    MenuText = winuser::COLOR_MENUTEXT,
+    WindowText = winuser::COLOR_WINDOWTEXT,
+    CaptionText = winuser::COLOR_CAPTIONTEXT,
+    ActiveBorder = winuser::COLOR_ACTIVEBORDER,
+    InactiveBorder = winuser::COLOR_INACTIVEBORDER,
+    AppWorkspace = winuser::COLOR_APPWORKSPACE,
+    Highlight = winuser::COLOR_HIGHLIGHT,
+    HighlightText = winuser::COLOR_HIGHLIGHTTEXT,
+    BtnFace = winuser::COLOR_BTNFACE,
+    BtnShadow = winuser::COLOR_BTNSHADOW,
+    GrayText = winuser::COLOR_GRAYTEXT,
+    BtnText = winuser::COLOR_BTNTEXT,
+    InactiveCaptionText = winuser::COLOR_INACTIVECAPTIONTEXT,
+    BtnHighlight = winuser::COLOR_BTNHIGHLIGHT,
+    DarkShadow3D = winuser::COLOR_3DDKSHADOW,
+    Light3D = winuser::COLOR_3DLIGHT,
+    InfoText = winuser::COLOR_INFOTEXT,
+    InfoBk = winuser::COLOR_INFOBK,
+    HotLight = winuser::COLOR_HOTLIGHT,
+    GradientActiveCaption = winuser::COLOR_GRADIENTACTIVECAPTION,
+    GradientInactiveCaption = winuser::COLOR_GRADIENTINACTIVECAPTION,
+    MenuHighlight = winuser::COLOR_MENUHILIGHT,
+    MenuBar = winuser::COLOR_MENUBAR,
+}
+
+impl SysColor {
+    /// Get the current value of this system color.
+    #[inline]
+    pub fn color(self) -> Color {
+        Color::from_colorref(unsafe { winuser::GetSysColor(self as i32) })
+    }
+}
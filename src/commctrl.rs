@@ -43,10 +43,25 @@
  * ----------------------------------------------------------------------------------
  */
 
-use core::mem;
+use crate::{
+    mutexes::Mutex, DroplessWindow, GenericWindow, WStr, WString, WeakWindow, Window, WindowStyle,
+};
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::{
+    mem,
+    ptr::NonNull,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+use cty::c_int;
+use euclid::default::Rect;
 use winapi::{
-    shared::minwindef::{DWORD, FALSE},
-    um::commctrl::*,
+    shared::{
+        basetsd::{DWORD_PTR, UINT_PTR},
+        minwindef::{DWORD, FALSE, LPARAM, LRESULT, UINT, WPARAM},
+        ntdef::WCHAR,
+        windef::{HWND, HWND__},
+    },
+    um::{commctrl::*, errhandlingapi, winuser},
 };
 
 bitflags::bitflags! {
@@ -54,6 +69,21 @@ bitflags::bitflags! {
     pub struct ControlClasses : DWORD {
         const ANIMATE_CLASS = ICC_ANIMATE_CLASS;
         const BAR_CLASSES = ICC_BAR_CLASSES;
+        const COOL_CLASSES = ICC_COOL_CLASSES;
+        const DATE_CLASSES = ICC_DATE_CLASSES;
+        const HOTKEY_CLASS = ICC_HOTKEY_CLASS;
+        const INTERNET_CLASSES = ICC_INTERNET_CLASSES;
+        const LINK_CLASS = ICC_LINK_CLASS;
+        const LISTVIEW_CLASSES = ICC_LISTVIEW_CLASSES;
+        const NATIVEFNTCTL_CLASS = ICC_NATIVEFNTCTL_CLASS;
+        const PAGESCROLLER_CLASS = ICC_PAGESCROLLER_CLASS;
+        const PROGRESS_CLASS = ICC_PROGRESS_CLASS;
+        const STANDARD_CLASSES = ICC_STANDARD_CLASSES;
+        const TAB_CLASSES = ICC_TAB_CLASSES;
+        const TREEVIEW_CLASSES = ICC_TREEVIEW_CLASSES;
+        const UPDOWN_CLASS = ICC_UPDOWN_CLASS;
+        const USEREX_CLASSES = ICC_USEREX_CLASSES;
+        const WIN95_CLASSES = ICC_WIN95_CLASSES;
     }
 }
 
@@ -72,3 +102,445 @@ pub fn init_commctrl(classes: ControlClasses) -> crate::Result<()> {
         Ok(())
     }
 }
+
+// set the text of a control through the wide version of WM_SETTEXT
+fn set_control_text(control: &dyn GenericWindow, text: &WStr) -> crate::Result<()> {
+    if unsafe {
+        winuser::SendMessageW(
+            control.try_hwnd()?.as_mut(),
+            winuser::WM_SETTEXT,
+            0,
+            text.as_ptr() as LPARAM,
+        )
+    } == 0
+    {
+        Err(crate::Error::StaticMsg(
+            "The control did not accept the new text",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// A button control.
+pub struct Button {
+    window: Window,
+    id: u16,
+}
+
+impl Button {
+    // create a button with the given button style
+    fn with_style(
+        parent: &dyn GenericWindow,
+        text: &str,
+        bounds: Rect<c_int>,
+        id: u16,
+        button_style: DWORD,
+    ) -> crate::Result<Self> {
+        Ok(Self {
+            window: Window::new_control(
+                "BUTTON",
+                text,
+                WindowStyle::VISIBLE | WindowStyle::TAB_STOP,
+                button_style,
+                bounds,
+                parent,
+                id,
+            )?,
+            id,
+        })
+    }
+
+    /// Create a new push button as a child of a window. When it is clicked, the parent
+    /// receives a `WM_COMMAND` message with the ID in the low word of its `wParam`.
+    #[inline]
+    pub fn new(
+        parent: &dyn GenericWindow,
+        text: &str,
+        bounds: Rect<c_int>,
+        id: u16,
+    ) -> crate::Result<Self> {
+        Self::with_style(parent, text, bounds, id, winuser::BS_PUSHBUTTON)
+    }
+
+    /// Create a new check box as a child of a window. It is checked and unchecked
+    /// automatically when clicked.
+    #[inline]
+    pub fn check_box(
+        parent: &dyn GenericWindow,
+        text: &str,
+        bounds: Rect<c_int>,
+        id: u16,
+    ) -> crate::Result<Self> {
+        Self::with_style(parent, text, bounds, id, winuser::BS_AUTOCHECKBOX)
+    }
+
+    /// Get the control ID of this button.
+    #[inline]
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Set whether or not this button is checked. This has no effect on push buttons.
+    #[inline]
+    pub fn set_checked(&self, checked: bool) {
+        crate::send_message(
+            self,
+            winuser::BM_SETCHECK,
+            if checked {
+                winuser::BST_CHECKED
+            } else {
+                winuser::BST_UNCHECKED
+            },
+            0,
+        );
+    }
+
+    /// Tell whether or not this button is checked.
+    #[inline]
+    pub fn is_checked(&self) -> bool {
+        crate::send_message(self, winuser::BM_GETCHECK, 0, 0) == winuser::BST_CHECKED as _
+    }
+
+    /// Set this button's label.
+    #[inline]
+    pub fn set_text(&self, text: &str) -> crate::Result<()> {
+        self.set_text_wide(&WString::from(text))
+    }
+
+    /// Set this button's label to a wide string.
+    #[inline]
+    pub fn set_text_wide(&self, text: &WStr) -> crate::Result<()> {
+        set_control_text(self, text)
+    }
+}
+
+impl GenericWindow for Button {
+    #[inline]
+    fn hwnd(&self) -> NonNull<HWND__> {
+        self.window.hwnd()
+    }
+
+    #[inline]
+    fn weak_reference(&self) -> WeakWindow {
+        self.window.weak_reference()
+    }
+}
+
+/// A single-line text box control.
+pub struct Edit {
+    window: Window,
+    id: u16,
+}
+
+impl Edit {
+    /// Create a new text box as a child of a window, containing the given text.
+    #[inline]
+    pub fn new(
+        parent: &dyn GenericWindow,
+        text: &str,
+        bounds: Rect<c_int>,
+        id: u16,
+    ) -> crate::Result<Self> {
+        Ok(Self {
+            window: Window::new_control(
+                "EDIT",
+                text,
+                WindowStyle::VISIBLE | WindowStyle::BORDER | WindowStyle::TAB_STOP,
+                winuser::ES_LEFT | winuser::ES_AUTOHSCROLL,
+                bounds,
+                parent,
+                id,
+            )?,
+            id,
+        })
+    }
+
+    /// Get the control ID of this text box.
+    #[inline]
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Get the text in this text box.
+    pub fn text(&self) -> crate::Result<String> {
+        let mut hwnd = self.try_hwnd()?;
+
+        // GetWindowTextLength returns zero both for empty text and on failure
+        unsafe { errhandlingapi::SetLastError(0) };
+        let len = unsafe { winuser::GetWindowTextLengthW(hwnd.as_mut()) };
+        if len == 0 {
+            return match unsafe { errhandlingapi::GetLastError() } {
+                0 => Ok(String::new()),
+                _ => Err(crate::win32_error(
+                    crate::Win32Function::GetWindowTextLengthW,
+                )),
+            };
+        }
+
+        // leave room for the NUL terminator
+        let mut buffer: Vec<WCHAR> = vec![0; len as usize + 1];
+        let copied = unsafe {
+            winuser::SendMessageW(
+                hwnd.as_mut(),
+                winuser::WM_GETTEXT,
+                buffer.len() as WPARAM,
+                buffer.as_mut_ptr() as LPARAM,
+            )
+        } as usize;
+
+        Ok(String::from_utf16(&buffer[..copied.min(len as usize)])?)
+    }
+
+    /// Select all of the text in this text box.
+    #[inline]
+    pub fn select_all(&self) {
+        crate::send_message(self, winuser::EM_SETSEL as UINT, 0, -1);
+    }
+
+    /// Set whether or not the user can change the text in this text box.
+    #[inline]
+    pub fn set_readonly(&self, readonly: bool) {
+        crate::send_message(
+            self,
+            winuser::EM_SETREADONLY as UINT,
+            crate::wboolify(readonly) as WPARAM,
+            0,
+        );
+    }
+
+    /// Set the text in this text box.
+    #[inline]
+    pub fn set_text(&self, text: &str) -> crate::Result<()> {
+        self.set_text_wide(&WString::from(text))
+    }
+
+    /// Set the text in this text box to a wide string.
+    #[inline]
+    pub fn set_text_wide(&self, text: &WStr) -> crate::Result<()> {
+        set_control_text(self, text)
+    }
+}
+
+impl GenericWindow for Edit {
+    #[inline]
+    fn hwnd(&self) -> NonNull<HWND__> {
+        self.window.hwnd()
+    }
+
+    #[inline]
+    fn weak_reference(&self) -> WeakWindow {
+        self.window.weak_reference()
+    }
+}
+
+// a closure that handles messages for a subclassed window
+pub(crate) type SubclassCallback =
+    Box<dyn FnMut(&DroplessWindow, UINT, WPARAM, LPARAM) -> Option<LRESULT>>;
+
+// the closure behind a subclass, along with a flag to catch re-entrant calls
+struct Subclass {
+    callback: SubclassCallback,
+    running: bool,
+}
+
+// each subclass gets its own ID, so that a window can be subclassed several times
+static NEXT_SUBCLASS_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// A subclass installed on a window by `GenericWindow::subclass`. The subclass is removed
+/// when this is dropped.
+pub struct SubclassGuard {
+    hwnd: Mutex<AtomicPtr<HWND__>>,
+    id: UINT_PTR,
+    subclass: *mut Subclass,
+}
+
+impl SubclassGuard {
+    // install a subclass on a window
+    pub(crate) fn install(hwnd: HWND, callback: SubclassCallback) -> crate::Result<Self> {
+        let id = NEXT_SUBCLASS_ID.fetch_add(1, Ordering::Relaxed);
+        let subclass = Box::into_raw(Box::new(Subclass {
+            callback,
+            running: false,
+        }));
+
+        if unsafe { SetWindowSubclass(hwnd, Some(subclass_trampoline), id, subclass as DWORD_PTR) }
+            == FALSE
+        {
+            // the subclass was never installed, so nothing else can hold the pointer
+            mem::drop(unsafe { Box::from_raw(subclass) });
+            Err(crate::Error::StaticMsg("Unable to subclass the window"))
+        } else {
+            Ok(Self {
+                hwnd: Mutex::new(AtomicPtr::new(hwnd)),
+                id,
+                subclass,
+            })
+        }
+    }
+}
+
+impl Drop for SubclassGuard {
+    fn drop(&mut self) {
+        // if the window has already been destroyed, the trampoline removed the subclass
+        // and this fails harmlessly
+        unsafe {
+            RemoveWindowSubclass(
+                *self.hwnd.lock().get_mut(),
+                Some(subclass_trampoline),
+                self.id,
+            )
+        };
+        mem::drop(unsafe { Box::from_raw(self.subclass) });
+    }
+}
+
+unsafe extern "system" fn subclass_trampoline(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    id: UINT_PTR,
+    ref_data: DWORD_PTR,
+) -> LRESULT {
+    // this is re-entered by messages sent from inside of the callback, so only the
+    // callback itself is ever borrowed, and only while it runs
+    let subclass = ref_data as *mut Subclass;
+
+    // subclasses must be removed before their window is destroyed
+    if msg == winuser::WM_NCDESTROY {
+        RemoveWindowSubclass(hwnd, Some(subclass_trampoline), id);
+        return DefSubclassProc(hwnd, msg, wparam, lparam);
+    }
+
+    // the closure can't be borrowed twice, so messages sent from inside of it skip it
+    if (*subclass).running {
+        return DefSubclassProc(hwnd, msg, wparam, lparam);
+    }
+
+    let window = DroplessWindow::new(hwnd);
+    (*subclass).running = true;
+    let result = {
+        let callback = &mut (*subclass).callback;
+        callback(&window, msg, wparam, lparam)
+    };
+    (*subclass).running = false;
+
+    match result {
+        Some(result) => result,
+        None => DefSubclassProc(hwnd, msg, wparam, lparam),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use core::{cell::Cell, ptr};
+
+    const TEST_MESSAGE: UINT = winuser::WM_USER + 1;
+
+    // create a hidden top-level STATIC window to subclass
+    fn create_static() -> DroplessWindow {
+        let class = WString::from("STATIC");
+        let text = WString::from("porcupine");
+        let hwnd = unsafe {
+            winuser::CreateWindowExW(
+                0,
+                class.as_ptr(),
+                text.as_ptr(),
+                winuser::WS_OVERLAPPED,
+                0,
+                0,
+                100,
+                100,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        assert!(!hwnd.is_null());
+        unsafe { DroplessWindow::new(hwnd) }
+    }
+
+    fn send(window: &DroplessWindow, msg: UINT) -> LRESULT {
+        unsafe { winuser::SendMessageW(window.hwnd().as_mut(), msg, 0, 0) }
+    }
+
+    // a subclass that answers TEST_MESSAGE, counts its calls, and holds an Rc so that
+    // tests can tell when it has been dropped
+    fn counting_subclass(
+        window: &DroplessWindow,
+        calls: &Rc<Cell<u32>>,
+        alive: &Rc<()>,
+    ) -> SubclassGuard {
+        let calls = calls.clone();
+        let alive = alive.clone();
+        window
+            .subclass(move |_, msg, _, _| {
+                let _ = &alive;
+                calls.set(calls.get() + 1);
+                if msg == TEST_MESSAGE {
+                    Some(42)
+                } else {
+                    None
+                }
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn dropping_guard_removes_subclass() {
+        let window = create_static();
+        let calls = Rc::new(Cell::new(0));
+        let alive = Rc::new(());
+
+        let guard = counting_subclass(&window, &calls, &alive);
+        assert_eq!(send(&window, TEST_MESSAGE), 42);
+        assert_eq!(Rc::strong_count(&alive), 2);
+
+        mem::drop(guard);
+        // the closure has been freed, and no longer sees messages
+        assert_eq!(Rc::strong_count(&alive), 1);
+        let calls_before = calls.get();
+        assert_eq!(send(&window, TEST_MESSAGE), 0);
+        assert_eq!(calls.get(), calls_before);
+
+        window.destroy().unwrap();
+    }
+
+    #[test]
+    fn destroying_window_before_guard() {
+        let window = create_static();
+        let calls = Rc::new(Cell::new(0));
+        let alive = Rc::new(());
+
+        let guard = counting_subclass(&window, &calls, &alive);
+        window.destroy().unwrap();
+
+        // WM_NCDESTROY removed the subclass, but the guard still owns the closure
+        assert_eq!(Rc::strong_count(&alive), 2);
+        mem::drop(guard);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn unhandled_messages_reach_window_procedure() {
+        let window = create_static();
+        let calls = Rc::new(Cell::new(0));
+        let alive = Rc::new(());
+
+        let _guard = counting_subclass(&window, &calls, &alive);
+        let calls_before = calls.get();
+
+        // the closure returns None, so the STATIC window procedure answers
+        assert_eq!(
+            send(&window, winuser::WM_GETTEXTLENGTH),
+            "porcupine".len() as LRESULT
+        );
+        assert_eq!(calls.get(), calls_before + 1);
+
+        window.destroy().unwrap();
+    }
+}
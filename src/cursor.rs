@@ -0,0 +1,105 @@
+/* -----------------------------------------------------------------------------------
+ * src/cursor.rs - Functions for manipulating the mouse cursor.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use core::ptr;
+use winapi::{shared::ntdef::LPCWSTR, um::winuser};
+
+/// The cursors provided by the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemCursor {
+    AppStarting,
+    Arrow,
+    Cross,
+    Hand,
+    Help,
+    IBeam,
+    No,
+    SizeAll,
+    SizeNESW,
+    SizeNS,
+    SizeNWSE,
+    SizeWE,
+    UpArrow,
+    Wait,
+}
+
+impl SystemCursor {
+    // the resource identifier for this cursor
+    #[inline]
+    pub(crate) fn resource(self) -> LPCWSTR {
+        match self {
+            Self::AppStarting => winuser::IDC_APPSTARTING,
+            Self::Arrow => winuser::IDC_ARROW,
+            Self::Cross => winuser::IDC_CROSS,
+            Self::Hand => winuser::IDC_HAND,
+            Self::Help => winuser::IDC_HELP,
+            Self::IBeam => winuser::IDC_IBEAM,
+            Self::No => winuser::IDC_NO,
+            Self::SizeAll => winuser::IDC_SIZEALL,
+            Self::SizeNESW => winuser::IDC_SIZENESW,
+            Self::SizeNS => winuser::IDC_SIZENS,
+            Self::SizeNWSE => winuser::IDC_SIZENWSE,
+            Self::SizeWE => winuser::IDC_SIZEWE,
+            Self::UpArrow => winuser::IDC_UPARROW,
+            Self::Wait => winuser::IDC_WAIT,
+        }
+    }
+}
+
+/// Set the mouse cursor to one of the system cursors.
+///
+/// Windows resets the cursor to the window class's cursor whenever the mouse moves,
+/// unless the window handles `WM_SETCURSOR`. To keep the cursor, call this function in
+/// response to `WM_SETCURSOR` and return `TRUE` from the window procedure. See
+/// `decode_set_cursor` for reading that message's parameters.
+pub fn set_cursor(cursor: SystemCursor) -> crate::Result<()> {
+    let hcursor = unsafe { winuser::LoadCursorW(ptr::null_mut(), cursor.resource()) };
+    if hcursor.is_null() {
+        Err(crate::win32_error(crate::Win32Function::LoadCursorW))
+    } else {
+        unsafe { winuser::SetCursor(hcursor) };
+        Ok(())
+    }
+}
@@ -43,7 +43,9 @@
  * ----------------------------------------------------------------------------------
  */
 
-use crate::{mutexes::Mutex, Bitmap, Brush, GenericWindow, Pen, WeakWindow};
+use crate::{
+    mutexes::Mutex, Bitmap, Brush, Color, GenericWindow, Pen, Region, WString, WeakWindow,
+};
 use alloc::sync::Weak;
 use core::{
     option::Option,
@@ -51,13 +53,13 @@ use core::{
     sync::atomic::AtomicPtr,
 };
 use cty::c_int;
-use euclid::default::{Point2D, Rect};
+use euclid::default::{Point2D, Rect, Size2D};
 use maybe_uninit::MaybeUninit;
 use winapi::{
     ctypes::c_void,
     shared::{
-        minwindef::DWORD,
-        windef::{HBITMAP__, HDC__},
+        minwindef::{DWORD, HIWORD, LOWORD},
+        windef::{HBITMAP__, HDC__, HWND__, POINT, SIZE},
     },
     um::{
         wingdi,
@@ -72,6 +74,15 @@ pub enum ArcDirection {
     CounterClockwise,
 }
 
+/// The rule used to decide which parts of a self-intersecting polygon are filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum PolyFillMode {
+    /// Fill areas between odd and even numbered sides. The middle of a star is not filled.
+    Alternate,
+    /// Fill any area with a non-zero winding number. The middle of a star is filled.
+    Winding,
+}
+
 // GDI objects that can be stored in a device context
 enum DeviceContextStorage {
     Bitmap(Weak<Mutex<AtomicPtr<HBITMAP__>>>),
@@ -87,6 +98,91 @@ enum DeviceContextType {
         old_object: Option<Mutex<AtomicPtr<c_void>>>,
         storage: Option<DeviceContextStorage>,
     },
+    // the raw handle is kept, since a DroplessWindow's weak reference dies with it
+    WindowDC {
+        owner: Mutex<AtomicPtr<HWND__>>,
+    },
+    // owned by something else, and not released on drop
+    #[cfg_attr(not(feature = "uxtheme"), allow(dead_code))]
+    Borrowed,
+}
+
+bitflags::bitflags! {
+    #[doc = "Flags for getting a device context with `get_dc_ex`"]
+    pub struct GetDcExFlags : DWORD {
+        const NONE = 0;
+        const WINDOW = winuser::DCX_WINDOW;
+        const CACHE = winuser::DCX_CACHE;
+        const PARENT_CLIP = winuser::DCX_PARENTCLIP;
+        const CLIP_SIBLINGS = winuser::DCX_CLIPSIBLINGS;
+        const CLIP_CHILDREN = winuser::DCX_CLIPCHILDREN;
+        const NO_RESET_ATTRS = winuser::DCX_NORESETATTRS;
+        const LOCK_WINDOW_UPDATE = winuser::DCX_LOCKWINDOWUPDATE;
+        const EXCLUDE_RGN = winuser::DCX_EXCLUDERGN;
+        const INTERSECT_RGN = winuser::DCX_INTERSECTRGN;
+        const INTERSECT_UPDATE = winuser::DCX_INTERSECTUPDATE;
+        const VALIDATE = winuser::DCX_VALIDATE;
+    }
+}
+
+/// A GDI object selected into a device context by `push_pen` or `push_brush`. When this
+/// is dropped, the object that was selected before is selected again. Both the DC and
+/// the selected object are borrowed, so the object can't be deleted while it is still
+/// selected.
+pub struct SelectGuard<'a> {
+    dc: &'a DeviceContext,
+    old_object: *mut c_void,
+}
+
+impl<'a> SelectGuard<'a> {
+    // select an object into a DC, remembering the old one
+    fn select(dc: &'a DeviceContext, object: *mut c_void) -> Self {
+        let old_object = unsafe { wingdi::SelectObject(dc.hdc().as_mut(), object) };
+        Self { dc, old_object }
+    }
+}
+
+impl<'a> Drop for SelectGuard<'a> {
+    fn drop(&mut self) {
+        // if the selection failed, there is nothing to put back
+        if !self.old_object.is_null() && self.old_object != wingdi::HGDI_ERROR {
+            unsafe { wingdi::SelectObject(self.dc.hdc().as_mut(), self.old_object) };
+        }
+    }
+}
+
+/// A saved state of a device context, created by `DeviceContext::save`. The state is
+/// restored when this is dropped.
+pub struct DcStateGuard<'a> {
+    dc: &'a DeviceContext,
+    saved: c_int,
+    restored: bool,
+}
+
+impl<'a> DcStateGuard<'a> {
+    /// Restore the DC to the saved state now, reporting any error.
+    #[inline]
+    pub fn restore(mut self) -> crate::Result<()> {
+        self.restored = true;
+        self.restore_dc()
+    }
+
+    // restoring by the saved ID also pops any states saved after this one
+    fn restore_dc(&self) -> crate::Result<()> {
+        if unsafe { wingdi::RestoreDC(self.dc.hdc().as_mut(), self.saved) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::RestoreDC))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a> Drop for DcStateGuard<'a> {
+    fn drop(&mut self) {
+        if !self.restored {
+            let _ = self.restore_dc();
+        }
+    }
 }
 
 /// A drawing context.
@@ -124,6 +220,11 @@ impl Drop for DeviceContext {
 
                 unsafe { wingdi::DeleteDC(*hdc.get_mut()) };
             }
+            DeviceContextType::WindowDC { ref owner } => {
+                // if the window has already been destroyed, this fails harmlessly
+                unsafe { winuser::ReleaseDC(*owner.lock().get_mut(), *hdc.get_mut()) };
+            }
+            DeviceContextType::Borrowed => {}
         }
     }
 }
@@ -142,7 +243,7 @@ impl DeviceContext {
     /// Start painting with a new DC.
     pub fn begin_paint<T: GenericWindow + ?Sized>(hwnd: &T) -> crate::Result<Self> {
         let mut ps: MaybeUninit<PAINTSTRUCT> = MaybeUninit::uninit();
-        let hdc = unsafe { winuser::BeginPaint(hwnd.hwnd().as_mut(), ps.as_mut_ptr()) };
+        let hdc = unsafe { winuser::BeginPaint(hwnd.try_hwnd()?.as_mut(), ps.as_mut_ptr()) };
 
         if hdc.is_null() {
             Err(crate::win32_error(crate::Win32Function::BeginPaint))
@@ -157,6 +258,75 @@ impl DeviceContext {
         }
     }
 
+    /// Get a DC for drawing on a window's client area outside of `WM_PAINT`, such as for
+    /// drawing a selection rectangle while the mouse is dragged. The DC is released when
+    /// it is dropped.
+    pub fn get_dc<T: GenericWindow + ?Sized>(window: &T) -> crate::Result<Self> {
+        let mut hwnd = window.try_hwnd()?;
+        let hdc = unsafe { winuser::GetDC(hwnd.as_mut()) };
+
+        if hdc.is_null() {
+            Err(crate::win32_error(crate::Win32Function::GetDC))
+        } else {
+            Ok(Self {
+                hdc: Mutex::new(AtomicPtr::new(hdc)),
+                kind: DeviceContextType::WindowDC {
+                    owner: Mutex::new(AtomicPtr::new(hwnd.as_ptr())),
+                },
+            })
+        }
+    }
+
+    /// Get a DC for drawing on a window outside of `WM_PAINT`, clipped to a region. If a
+    /// region is given and the flags include neither `INTERSECT_RGN` nor `EXCLUDE_RGN`,
+    /// the DC's visible area is intersected with the region. The region is copied, so it
+    /// can still be used afterwards. The DC is released when it is dropped.
+    pub fn get_dc_ex<T: GenericWindow + ?Sized>(
+        window: &T,
+        clip: Option<&Region>,
+        flags: GetDcExFlags,
+    ) -> crate::Result<Self> {
+        let mut flags = flags;
+        let mut hwnd = window.try_hwnd()?;
+
+        // GetDCEx takes ownership of the region, so give it a copy
+        let clip = match clip {
+            Some(clip) => {
+                if !flags.intersects(GetDcExFlags::INTERSECT_RGN | GetDcExFlags::EXCLUDE_RGN) {
+                    flags |= GetDcExFlags::INTERSECT_RGN;
+                }
+                clip.try_clone()?.into_raw()
+            }
+            None => ptr::null_mut(),
+        };
+
+        let hdc = unsafe { winuser::GetDCEx(hwnd.as_mut(), clip, flags.bits()) };
+
+        if hdc.is_null() {
+            let err = crate::win32_error(crate::Win32Function::GetDCEx);
+            if !clip.is_null() {
+                unsafe { crate::delete_gdi_object(clip as *mut c_void, "region") };
+            }
+            Err(err)
+        } else {
+            Ok(Self {
+                hdc: Mutex::new(AtomicPtr::new(hdc)),
+                kind: DeviceContextType::WindowDC {
+                    owner: Mutex::new(AtomicPtr::new(hwnd.as_ptr())),
+                },
+            })
+        }
+    }
+
+    // wrap a DC that is owned by something else
+    #[cfg(feature = "uxtheme")]
+    pub(crate) fn borrowed(hdc: winapi::shared::windef::HDC) -> Self {
+        Self {
+            hdc: Mutex::new(AtomicPtr::new(hdc)),
+            kind: DeviceContextType::Borrowed,
+        }
+    }
+
     /// Create a compatible DC for another DC.
     pub fn create_compatible(&self) -> crate::Result<Self> {
         let hdc = unsafe { wingdi::CreateCompatibleDC(self.hdc().as_mut()) };
@@ -174,6 +344,47 @@ impl DeviceContext {
         }
     }
 
+    /// Create a bitmap in the same format as this device context. Bitmaps like this are
+    /// the fastest to copy onto the DC, which makes them the right choice for
+    /// double-buffering.
+    ///
+    /// This should be called on a window or screen DC. A memory DC starts out with a
+    /// one-pixel monochrome bitmap selected into it, so a bitmap compatible with it is
+    /// monochrome as well.
+    pub fn create_compatible_bitmap(&self, size: Size2D<c_int>) -> crate::Result<Bitmap> {
+        let hbitmap =
+            unsafe { wingdi::CreateCompatibleBitmap(self.hdc().as_mut(), size.width, size.height) };
+
+        if hbitmap.is_null() {
+            Err(crate::win32_error(
+                crate::Win32Function::CreateCompatibleBitmap,
+            ))
+        } else {
+            Bitmap::from_hbitmap(self, hbitmap)
+        }
+    }
+
+    /// Create an information context for the display. This is a lightweight DC that
+    /// cannot be drawn on, but can be used to query text metrics and device capabilities
+    /// without a window.
+    pub fn information_dc() -> crate::Result<Self> {
+        let driver = crate::constant_text!("DISPLAY");
+        let hdc =
+            unsafe { wingdi::CreateICW(driver.as_ptr(), ptr::null(), ptr::null(), ptr::null()) };
+
+        if hdc.is_null() {
+            Err(crate::win32_error(crate::Win32Function::CreateIC))
+        } else {
+            Ok(Self {
+                hdc: Mutex::new(AtomicPtr::new(hdc)),
+                kind: DeviceContextType::OwnsGDIObject {
+                    old_object: None,
+                    storage: None,
+                },
+            })
+        }
+    }
+
     /// Set the pen for this DC.
     #[inline]
     pub fn set_pen(&self, pen: &Pen) {
@@ -188,10 +399,41 @@ impl DeviceContext {
         };
     }
 
+    /// Select a pen into this DC until the returned guard is dropped, at which point the
+    /// pen that was selected before is put back.
+    #[inline]
+    pub fn push_pen<'a>(&'a self, pen: &'a Pen) -> SelectGuard<'a> {
+        SelectGuard::select(self, unsafe { pen.hpen().as_ptr() as *mut c_void })
+    }
+
+    /// Select a brush into this DC until the returned guard is dropped, at which point
+    /// the brush that was selected before is put back.
+    #[inline]
+    pub fn push_brush<'a>(&'a self, brush: &'a Brush) -> SelectGuard<'a> {
+        SelectGuard::select(self, unsafe { brush.hbrush().as_ptr() as *mut c_void })
+    }
+
+    /// Save the state of this DC, including its selected objects, transform and clipping
+    /// region. The state is restored when the returned guard is dropped, or when
+    /// `DcStateGuard::restore` is called.
+    #[inline]
+    pub fn save(&self) -> crate::Result<DcStateGuard<'_>> {
+        match unsafe { wingdi::SaveDC(self.hdc().as_mut()) } {
+            0 => Err(crate::win32_error(crate::Win32Function::SaveDC)),
+            saved => Ok(DcStateGuard {
+                dc: self,
+                saved,
+                restored: false,
+            }),
+        }
+    }
+
     /// Turn a compatible DC into a bitmap DC.
     pub fn set_bitmap(&mut self, bitmap: &Bitmap) -> crate::Result<()> {
         match self.kind {
-            DeviceContextType::Painter { .. } => Err(crate::Error::NoGDIStorage),
+            DeviceContextType::Painter { .. }
+            | DeviceContextType::WindowDC { .. }
+            | DeviceContextType::Borrowed => Err(crate::Error::NoGDIStorage),
             DeviceContextType::OwnsGDIObject {
                 ref mut old_object,
                 ref mut storage,
@@ -215,6 +457,25 @@ impl DeviceContext {
         }
     }
 
+    // temporarily select the original object back into this DC while running a function,
+    // for Win32 functions that require the stored object to not be selected
+    pub(crate) fn without_storage<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        match self.kind {
+            DeviceContextType::OwnsGDIObject {
+                old_object: Some(ref o),
+                ..
+            } => {
+                let hdc = unsafe { self.hdc().as_mut() };
+                let old = *o.lock().get_mut();
+                let current = unsafe { wingdi::SelectObject(hdc, old) };
+                let res = f();
+                unsafe { wingdi::SelectObject(hdc, current) };
+                res
+            }
+            _ => f(),
+        }
+    }
+
     /// Get a handle to this DC.
     ///
     /// # Safety
@@ -255,6 +516,56 @@ impl DeviceContext {
         }
     }
 
+    /// Fill an area with copies of a bitmap, starting from the area's top left corner.
+    /// Tiles along the right and bottom edges are cut off so nothing is drawn outside of
+    /// the area.
+    pub fn tile_bitmap(&self, bitmap: &Bitmap, area: Rect<c_int>) -> crate::Result<()> {
+        let Size2D {
+            width: tile_width,
+            height: tile_height,
+            ..
+        } = bitmap.size();
+        if tile_width <= 0 || tile_height <= 0 {
+            return Ok(());
+        }
+
+        let mut y = area.origin.y;
+        while y < area.max_y() {
+            let height = tile_height.min(area.max_y() - y);
+
+            let mut x = area.origin.x;
+            while x < area.max_x() {
+                let width = tile_width.min(area.max_x() - x);
+                self.copy_from(
+                    bitmap.dc(),
+                    euclid::rect(0, 0, width, height),
+                    Point2D::new(x, y),
+                    CopyOperation::SrcCopy,
+                )?;
+                x += tile_width;
+            }
+
+            y += tile_height;
+        }
+
+        Ok(())
+    }
+
+    /// Flush the calling thread's batch of GDI drawing operations.
+    ///
+    /// GDI batches drawing calls, and the batch is normally flushed when the thread
+    /// returns to the message loop. Drawing done elsewhere (for instance, from a timer
+    /// callback or a worker thread that keeps drawing) may not appear on screen until
+    /// this is called.
+    #[inline]
+    pub fn flush(&self) -> crate::Result<()> {
+        if unsafe { wingdi::GdiFlush() } == 0 {
+            Err(crate::win32_error(crate::Win32Function::GdiFlush))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Move this DC to a coordinate point.
     pub fn move_to(&self, p: Point2D<c_int>) -> crate::Result<()> {
         if unsafe { wingdi::MoveToEx(self.hdc().as_mut(), p.x, p.y, ptr::null_mut()) } == 0 {
@@ -264,16 +575,35 @@ impl DeviceContext {
         }
     }
 
-    /// Draw a line between two points.
-    pub fn draw_line(&self, p1: Point2D<c_int>, p2: Point2D<c_int>) -> crate::Result<()> {
-        self.move_to(p1)?;
-        if unsafe { wingdi::LineTo(self.hdc().as_mut(), p2.x, p2.y) } == 0 {
+    /// Get the current position of this DC.
+    pub fn current_position(&self) -> crate::Result<Point2D<c_int>> {
+        let mut pt: MaybeUninit<POINT> = MaybeUninit::uninit();
+        if unsafe { wingdi::GetCurrentPositionEx(self.hdc().as_mut(), pt.as_mut_ptr()) } == 0 {
+            Err(crate::win32_error(
+                crate::Win32Function::GetCurrentPositionEx,
+            ))
+        } else {
+            let pt = unsafe { pt.assume_init() };
+            Ok(Point2D::new(pt.x, pt.y))
+        }
+    }
+
+    /// Draw a line from the current position to a point, which becomes the new current
+    /// position.
+    pub fn line_to(&self, p: Point2D<c_int>) -> crate::Result<()> {
+        if unsafe { wingdi::LineTo(self.hdc().as_mut(), p.x, p.y) } == 0 {
             Err(crate::win32_error(crate::Win32Function::LineTo))
         } else {
             Ok(())
         }
     }
 
+    /// Draw a line between two points.
+    pub fn draw_line(&self, p1: Point2D<c_int>, p2: Point2D<c_int>) -> crate::Result<()> {
+        self.move_to(p1)?;
+        self.line_to(p2)
+    }
+
     /// Draw an arc between two points, enclosed in a bounding rect.
     pub fn draw_arc(
         &self,
@@ -301,6 +631,64 @@ impl DeviceContext {
         }
     }
 
+    /// Draw a pie slice: the part of the ellipse inside of `bounds` between the radials
+    /// ending at `p1` and `p2`. It is outlined with the current pen and filled with the
+    /// current brush.
+    pub fn draw_pie(
+        &self,
+        bounds: Rect<c_int>,
+        p1: Point2D<c_int>,
+        p2: Point2D<c_int>,
+    ) -> crate::Result<()> {
+        if unsafe {
+            wingdi::Pie(
+                self.hdc().as_mut(),
+                bounds.origin.x,
+                bounds.origin.y,
+                bounds.origin.x + bounds.size.width,
+                bounds.origin.y + bounds.size.height,
+                p1.x,
+                p1.y,
+                p2.x,
+                p2.y,
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::Pie))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Draw a chord: the part of the ellipse inside of `bounds` cut off by the line from
+    /// `p1` to `p2`. It is outlined with the current pen and filled with the current
+    /// brush.
+    pub fn draw_chord(
+        &self,
+        bounds: Rect<c_int>,
+        p1: Point2D<c_int>,
+        p2: Point2D<c_int>,
+    ) -> crate::Result<()> {
+        if unsafe {
+            wingdi::Chord(
+                self.hdc().as_mut(),
+                bounds.origin.x,
+                bounds.origin.y,
+                bounds.origin.x + bounds.size.width,
+                bounds.origin.y + bounds.size.height,
+                p1.x,
+                p1.y,
+                p2.x,
+                p2.y,
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::Chord))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Set the arc direction of this item.
     #[inline]
     pub fn set_arc_direction(&self, dir: ArcDirection) -> crate::Result<()> {
@@ -320,6 +708,85 @@ impl DeviceContext {
         }
     }
 
+    /// Set the fill mode used for polygons drawn on this DC.
+    #[inline]
+    pub fn set_poly_fill_mode(&self, mode: PolyFillMode) -> crate::Result<()> {
+        if unsafe {
+            wingdi::SetPolyFillMode(
+                self.hdc().as_mut(),
+                match mode {
+                    PolyFillMode::Alternate => wingdi::ALTERNATE,
+                    PolyFillMode::Winding => wingdi::WINDING,
+                } as c_int,
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::SetPolyFillMode))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Switch this DC to the advanced graphics mode, which is needed for world transforms.
+    #[inline]
+    pub fn set_graphics_mode_advanced(&self) -> crate::Result<()> {
+        if unsafe { wingdi::SetGraphicsMode(self.hdc().as_mut(), wingdi::GM_ADVANCED as c_int) }
+            == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::SetGraphicsMode))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the world transform of this DC. A point `(x, y)` is drawn at
+    /// `(x * m11 + y * m21 + dx, x * m12 + y * m22 + dy)`. The DC must be in the advanced
+    /// graphics mode; see `set_graphics_mode_advanced`.
+    pub fn set_world_transform(
+        &self,
+        m11: f32,
+        m12: f32,
+        m21: f32,
+        m22: f32,
+        dx: f32,
+        dy: f32,
+    ) -> crate::Result<()> {
+        let xform = wingdi::XFORM {
+            eM11: m11,
+            eM12: m12,
+            eM21: m21,
+            eM22: m22,
+            eDx: dx,
+            eDy: dy,
+        };
+
+        if unsafe { wingdi::SetWorldTransform(self.hdc().as_mut(), &xform) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::SetWorldTransform))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Rotate everything drawn afterwards clockwise by `degrees` around `center`. This
+    /// switches the DC to the advanced graphics mode and replaces the current world
+    /// transform.
+    #[cfg(feature = "std")]
+    pub fn rotate(&self, degrees: f32, center: Point2D<c_int>) -> crate::Result<()> {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (cx, cy) = (center.x as f32, center.y as f32);
+
+        self.set_graphics_mode_advanced()?;
+        // move the center to the origin, rotate, then move it back
+        self.set_world_transform(
+            cos,
+            sin,
+            -sin,
+            cos,
+            cx - cx * cos + cy * sin,
+            cy - cx * sin - cy * cos,
+        )
+    }
+
     /// Draw a rectangle.
     #[inline]
     pub fn draw_rect(&self, rect: Rect<c_int>) -> crate::Result<()> {
@@ -339,6 +806,28 @@ impl DeviceContext {
         }
     }
 
+    /// Draw a rectangle with rounded corners. `corner` is the size of the ellipse used to
+    /// draw each corner.
+    #[inline]
+    pub fn draw_round_rect(&self, rect: Rect<c_int>, corner: Size2D<c_int>) -> crate::Result<()> {
+        if unsafe {
+            wingdi::RoundRect(
+                self.hdc().as_mut(),
+                rect.origin.x,
+                rect.origin.y,
+                rect.origin.x + rect.size.width,
+                rect.origin.y + rect.size.height,
+                corner.width,
+                corner.height,
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::RoundRect))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Draw an ellipse.
     #[inline]
     pub fn draw_ellipse(&self, bounding_rect: Rect<c_int>) -> crate::Result<()> {
@@ -358,9 +847,72 @@ impl DeviceContext {
         }
     }
 
+    /// Draw a string of text at a point, expanding tabs to the given tab stops. The tab
+    /// stops are pixel positions relative to the start of the text; if none are given,
+    /// tabs are expanded to every eight average character widths. Returns the size of
+    /// the drawn text.
+    pub fn tabbed_text_out(
+        &self,
+        pos: Point2D<c_int>,
+        text: &str,
+        tab_stops: &[c_int],
+    ) -> crate::Result<Size2D<c_int>> {
+        let text = WString::from(text);
+        let dimensions = unsafe {
+            winuser::TabbedTextOutW(
+                self.hdc().as_mut(),
+                pos.x,
+                pos.y,
+                text.as_ptr(),
+                text.as_slice().len() as c_int,
+                tab_stops.len() as c_int,
+                if tab_stops.is_empty() {
+                    ptr::null()
+                } else {
+                    tab_stops.as_ptr()
+                },
+                pos.x,
+            )
+        };
+
+        if dimensions == 0 {
+            Err(crate::win32_error(crate::Win32Function::TabbedTextOut))
+        } else {
+            let dimensions = dimensions as DWORD;
+            Ok(Size2D::new(
+                LOWORD(dimensions) as c_int,
+                HIWORD(dimensions) as c_int,
+            ))
+        }
+    }
+
+    /// Measure the size that a string of text takes up when drawn with the font that is
+    /// currently selected into this DC.
+    pub fn measure_text(&self, text: &str) -> crate::Result<Size2D<c_int>> {
+        let text = WString::from(text);
+        let mut size: MaybeUninit<SIZE> = MaybeUninit::zeroed();
+
+        if unsafe {
+            wingdi::GetTextExtentPoint32W(
+                self.hdc().as_mut(),
+                text.as_ptr(),
+                text.len() as c_int,
+                size.as_mut_ptr(),
+            )
+        } == 0
+        {
+            Err(crate::win32_error(
+                crate::Win32Function::GetTextExtentPoint32,
+            ))
+        } else {
+            let size = unsafe { size.assume_init() };
+            Ok(Size2D::new(size.cx, size.cy))
+        }
+    }
+
     /// Set the brush color.
-    pub fn set_brush_color(&self, r: u8, g: u8, b: u8) -> crate::Result<()> {
-        let clr = wingdi::RGB(r, g, b);
+    pub fn set_brush_color<C: Into<Color>>(&self, color: C) -> crate::Result<()> {
+        let clr = color.into().to_colorref();
         if unsafe { wingdi::SetDCBrushColor(self.hdc().as_mut(), clr) } == wingdi::CLR_INVALID {
             Err(crate::win32_error(crate::Win32Function::SetDCBrushColor))
         } else {
@@ -369,12 +921,30 @@ impl DeviceContext {
     }
 
     /// Set the pen color.
-    pub fn set_pen_color(&self, r: u8, g: u8, b: u8) -> crate::Result<()> {
-        let clr = wingdi::RGB(r, g, b);
-        if unsafe { wingdi::SetDCBrushColor(self.hdc().as_mut(), clr) } == wingdi::CLR_INVALID {
+    pub fn set_pen_color<C: Into<Color>>(&self, color: C) -> crate::Result<()> {
+        let clr = color.into().to_colorref();
+        if unsafe { wingdi::SetDCPenColor(self.hdc().as_mut(), clr) } == wingdi::CLR_INVALID {
             Err(crate::win32_error(crate::Win32Function::SetDCPenColor))
         } else {
             Ok(())
         }
     }
 }
+
+/// Get the maximum number of GDI calls that the calling thread batches before they are
+/// sent to the display. The batch limit is kept per thread.
+#[inline]
+pub fn gdi_batch_limit() -> DWORD {
+    unsafe { wingdi::GdiGetBatchLimit() }
+}
+
+/// Set the maximum number of GDI calls that the calling thread batches before they are
+/// sent to the display, and return the previous limit. A limit of 1 disables batching,
+/// and a limit of 0 restores the default. This only affects the calling thread.
+///
+/// A higher limit trades latency for throughput. Use `DeviceContext::flush` to send a
+/// batch early.
+#[inline]
+pub fn set_gdi_batch_limit(limit: DWORD) -> DWORD {
+    unsafe { wingdi::GdiSetBatchLimit(limit) }
+}
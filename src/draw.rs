@@ -45,7 +45,7 @@
 
 //! Pens and brushes
 
-use crate::mutexes::Mutex;
+use crate::{mutexes::Mutex, Color, SysColor};
 use core::{ptr::NonNull, sync::atomic::AtomicPtr};
 use cty::c_int;
 use winapi::{
@@ -54,7 +54,7 @@ use winapi::{
         minwindef::DWORD,
         windef::{HBRUSH__, HPEN__},
     },
-    um::wingdi::{self, RGB},
+    um::{wingdi, winuser},
 };
 
 /// The styles that a pen can have.
@@ -79,8 +79,8 @@ pub struct Pen {
 impl Pen {
     /// Create a new pen from a color, line width, and style.
     #[inline]
-    pub fn new(r: u8, g: u8, b: u8, width: u32, style: PenStyle) -> crate::Result<Self> {
-        let crref = RGB(r, g, b);
+    pub fn new<C: Into<Color>>(color: C, width: u32, style: PenStyle) -> crate::Result<Self> {
+        let crref = color.into().to_colorref();
         let hpen = unsafe { wingdi::CreatePen(style as DWORD as c_int, width as c_int, crref) };
         if hpen.is_null() {
             Err(crate::win32_error(crate::Win32Function::CreatePen))
@@ -108,31 +108,45 @@ impl Pen {
 impl Drop for Pen {
     #[inline]
     fn drop(&mut self) {
-        unsafe { wingdi::DeleteObject(*self.hpen.lock().get_mut() as *mut c_void) };
+        unsafe { crate::delete_gdi_object(*self.hpen.lock().get_mut() as *mut c_void, "pen") };
     }
 }
 
 /// A brush that can be used to paint onto the screen.
-#[repr(transparent)]
 pub struct Brush {
     hbrush: Mutex<AtomicPtr<HBRUSH__>>,
+    // system color brushes are owned by Windows and must not be deleted
+    stock: bool,
 }
 
 impl Brush {
     /// Create a new brush from a color.
     #[inline]
-    pub fn solid(r: u8, g: u8, b: u8) -> crate::Result<Self> {
-        let crref = RGB(r, g, b);
+    pub fn solid<C: Into<Color>>(color: C) -> crate::Result<Self> {
+        let crref = color.into().to_colorref();
         let hbrush = unsafe { wingdi::CreateSolidBrush(crref) };
         if hbrush.is_null() {
             Err(crate::win32_error(crate::Win32Function::CreateBrush))
         } else {
             Ok(Self {
                 hbrush: Mutex::new(AtomicPtr::new(hbrush)),
+                stock: false,
             })
         }
     }
 
+    /// Get the brush for one of the system colors. These brushes are cached by Windows,
+    /// and are not deleted when dropped.
+    #[inline]
+    pub fn system(color: SysColor) -> Self {
+        let hbrush = unsafe { winuser::GetSysColorBrush(color as c_int) };
+        debug_assert!(!hbrush.is_null());
+        Self {
+            hbrush: Mutex::new(AtomicPtr::new(hbrush)),
+            stock: true,
+        }
+    }
+
     /// Get the handle to this brush.
     #[inline]
     pub unsafe fn hbrush(&self) -> NonNull<HBRUSH__> {
@@ -146,6 +160,10 @@ impl Brush {
 impl Drop for Brush {
     #[inline]
     fn drop(&mut self) {
-        unsafe { wingdi::DeleteObject(*self.hbrush.lock().get_mut() as *mut c_void) };
+        if !self.stock {
+            unsafe {
+                crate::delete_gdi_object(*self.hbrush.lock().get_mut() as *mut c_void, "brush")
+            };
+        }
     }
 }
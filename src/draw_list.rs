@@ -0,0 +1,158 @@
+/* -----------------------------------------------------------------------------------
+ * src/draw_list.rs - Recorded drawing operations
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::{Bitmap, Color, DeviceContext};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use cty::c_int;
+use euclid::default::{Point2D, Rect};
+
+/// A single drawing operation recorded in a `DrawList`.
+#[derive(Clone)]
+pub enum DrawOp {
+    /// Draw a line between two points with the current pen.
+    Line(Point2D<c_int>, Point2D<c_int>),
+    /// Draw a rectangle with the current pen and brush.
+    Rect(Rect<c_int>),
+    /// Draw an ellipse inside of a bounding rectangle with the current pen and brush.
+    Ellipse(Rect<c_int>),
+    /// Draw a string of text at a point.
+    Text(Point2D<c_int>, String),
+    /// Copy a bitmap onto the DC, with its top left corner at a point.
+    Bitmap(Point2D<c_int>, Arc<Bitmap>),
+    /// Change the color of the DC's pen.
+    PenColor(Color),
+    /// Change the color of the DC's brush.
+    BrushColor(Color),
+}
+
+/// A list of drawing operations that can be recorded once and replayed onto a device
+/// context as many times as needed, such as on every `WM_PAINT`.
+#[derive(Clone, Default)]
+pub struct DrawList {
+    ops: Vec<DrawOp>,
+}
+
+impl DrawList {
+    /// Create a new, empty draw list.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an operation to the end of this list.
+    #[inline]
+    pub fn push(&mut self, op: DrawOp) {
+        self.ops.push(op);
+    }
+
+    /// Record a line between two points.
+    #[inline]
+    pub fn line(&mut self, p1: Point2D<c_int>, p2: Point2D<c_int>) {
+        self.push(DrawOp::Line(p1, p2));
+    }
+
+    /// Record a rectangle.
+    #[inline]
+    pub fn rect(&mut self, rect: Rect<c_int>) {
+        self.push(DrawOp::Rect(rect));
+    }
+
+    /// Record an ellipse.
+    #[inline]
+    pub fn ellipse(&mut self, bounding_rect: Rect<c_int>) {
+        self.push(DrawOp::Ellipse(bounding_rect));
+    }
+
+    /// Record a string of text.
+    #[inline]
+    pub fn text<S: Into<String>>(&mut self, pos: Point2D<c_int>, text: S) {
+        self.push(DrawOp::Text(pos, text.into()));
+    }
+
+    /// Record a bitmap. The bitmap is shared, so it can be kept and drawn elsewhere too.
+    #[inline]
+    pub fn bitmap(&mut self, pos: Point2D<c_int>, bitmap: Arc<Bitmap>) {
+        self.push(DrawOp::Bitmap(pos, bitmap));
+    }
+
+    /// Record a change of pen color.
+    #[inline]
+    pub fn pen_color<C: Into<Color>>(&mut self, color: C) {
+        self.push(DrawOp::PenColor(color.into()));
+    }
+
+    /// Record a change of brush color.
+    #[inline]
+    pub fn brush_color<C: Into<Color>>(&mut self, color: C) {
+        self.push(DrawOp::BrushColor(color.into()));
+    }
+
+    /// Remove every operation from this list.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+
+    /// The operations recorded in this list, in order.
+    #[inline]
+    pub fn ops(&self) -> &[DrawOp] {
+        &self.ops
+    }
+
+    /// Run every operation in this list against a device context, in order. This stops
+    /// at the first operation that fails.
+    pub fn replay(&self, dc: &DeviceContext) -> crate::Result<()> {
+        self.ops.iter().try_for_each(|op| match op {
+            DrawOp::Line(p1, p2) => dc.draw_line(*p1, *p2),
+            DrawOp::Rect(rect) => dc.draw_rect(*rect),
+            DrawOp::Ellipse(rect) => dc.draw_ellipse(*rect),
+            DrawOp::Text(_, text) if text.is_empty() => Ok(()),
+            DrawOp::Text(pos, text) => dc.tabbed_text_out(*pos, text, &[]).map(|_| ()),
+            DrawOp::Bitmap(pos, bitmap) => bitmap.blit_to(dc, *pos),
+            DrawOp::PenColor(color) => dc.set_pen_color(*color),
+            DrawOp::BrushColor(color) => dc.set_brush_color(*color),
+        })
+    }
+}
@@ -43,13 +43,17 @@
  * ----------------------------------------------------------------------------------
  */
 
-use alloc::{
-    string::{FromUtf8Error, String, ToString},
-    vec::Vec,
-};
+use crate::WStr;
+use alloc::string::{FromUtf16Error, FromUtf8Error, String, ToString};
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
 use core::{fmt, ptr};
 use winapi::{
-    shared::minwindef::DWORD,
+    shared::{
+        minwindef::DWORD,
+        ntdef::WCHAR,
+        winerror::{self, HRESULT},
+    },
     um::{errhandlingapi, winbase::*},
 };
 
@@ -91,6 +95,70 @@ pub enum Win32Function {
     GetCursorPos,
     CreatePen,
     CreateBrush,
+    LoadImage,
+    GetObjectW,
+    SetTimer,
+    CreateDIBSection,
+    GetDIBits,
+    GdiFlush,
+    SetWindowPos,
+    DestroyWindow,
+    BringWindowToTop,
+    ValidateRect,
+    CreateRectRgn,
+    GetWindowRgn,
+    SetLayeredWindowAttributes,
+    GetClientRect,
+    GetWindowRect,
+    ClientToScreen,
+    BufferedPaintInit,
+    BeginBufferedPaint,
+    GetDC,
+    OpenThemeData,
+    DrawThemeBackground,
+    SetFocus,
+    LoadCursorW,
+    CreateMenu,
+    CreatePopupMenu,
+    AppendMenuW,
+    SetMenu,
+    AttachThreadInput,
+    SetForegroundWindow,
+    GetKeyNameTextW,
+    CreateAcceleratorTableW,
+    KillTimer,
+    TabbedTextOut,
+    PostMessageA,
+    GetCurrentPositionEx,
+    CreateIC,
+    SendInput,
+    CreateWindowExW,
+    GetWindowTextLengthW,
+    MoveWindow,
+    PrintWindow,
+    CombineRgn,
+    GetDCEx,
+    GetMenuItemCount,
+    SetCursorPos,
+    ClipCursor,
+    UpdateLayeredWindow,
+    GetWindowModuleFileNameW,
+    RedrawWindow,
+    SetWindowsHookExW,
+    FindWindowW,
+    EnumWindows,
+    GetParent,
+    SetParent,
+    SetWindowTextW,
+    SaveDC,
+    RestoreDC,
+    Pie,
+    Chord,
+    RoundRect,
+    SetPolyFillMode,
+    SetGraphicsMode,
+    SetWorldTransform,
+    GetTextExtentPoint32,
     Other(&'static str),
 }
 
@@ -100,6 +168,70 @@ impl fmt::Display for Win32Function {
             f,
             "{}",
             match *self {
+                Self::GetTextExtentPoint32 => "GetTextExtentPoint32",
+                Self::SetWorldTransform => "SetWorldTransform",
+                Self::SetGraphicsMode => "SetGraphicsMode",
+                Self::SetPolyFillMode => "SetPolyFillMode",
+                Self::RoundRect => "RoundRect",
+                Self::Chord => "Chord",
+                Self::Pie => "Pie",
+                Self::RestoreDC => "RestoreDC",
+                Self::SaveDC => "SaveDC",
+                Self::SetWindowTextW => "SetWindowTextW",
+                Self::SetParent => "SetParent",
+                Self::GetParent => "GetParent",
+                Self::EnumWindows => "EnumWindows",
+                Self::FindWindowW => "FindWindowW",
+                Self::SetWindowsHookExW => "SetWindowsHookExW",
+                Self::RedrawWindow => "RedrawWindow",
+                Self::GetWindowModuleFileNameW => "GetWindowModuleFileNameW",
+                Self::UpdateLayeredWindow => "UpdateLayeredWindow",
+                Self::SetCursorPos => "SetCursorPos",
+                Self::ClipCursor => "ClipCursor",
+                Self::GetMenuItemCount => "GetMenuItemCount",
+                Self::CombineRgn => "CombineRgn",
+                Self::GetDCEx => "GetDCEx",
+                Self::PrintWindow => "PrintWindow",
+                Self::MoveWindow => "MoveWindow",
+                Self::GetWindowTextLengthW => "GetWindowTextLengthW",
+                Self::CreateWindowExW => "CreateWindowExW",
+                Self::SendInput => "SendInput",
+                Self::CreateIC => "CreateIC",
+                Self::GetCurrentPositionEx => "GetCurrentPositionEx",
+                Self::PostMessageA => "PostMessageA",
+                Self::TabbedTextOut => "TabbedTextOut",
+                Self::KillTimer => "KillTimer",
+                Self::CreateAcceleratorTableW => "CreateAcceleratorTableW",
+                Self::GetKeyNameTextW => "GetKeyNameTextW",
+                Self::AttachThreadInput => "AttachThreadInput",
+                Self::SetForegroundWindow => "SetForegroundWindow",
+                Self::CreateMenu => "CreateMenu",
+                Self::CreatePopupMenu => "CreatePopupMenu",
+                Self::AppendMenuW => "AppendMenuW",
+                Self::SetMenu => "SetMenu",
+                Self::LoadCursorW => "LoadCursorW",
+                Self::SetFocus => "SetFocus",
+                Self::OpenThemeData => "OpenThemeData",
+                Self::DrawThemeBackground => "DrawThemeBackground",
+                Self::GetDC => "GetDC",
+                Self::BeginBufferedPaint => "BeginBufferedPaint",
+                Self::BufferedPaintInit => "BufferedPaintInit",
+                Self::ClientToScreen => "ClientToScreen",
+                Self::GetClientRect => "GetClientRect",
+                Self::GetWindowRect => "GetWindowRect",
+                Self::SetLayeredWindowAttributes => "SetLayeredWindowAttributes",
+                Self::GetWindowRgn => "GetWindowRgn",
+                Self::CreateRectRgn => "CreateRectRgn",
+                Self::ValidateRect => "ValidateRect",
+                Self::BringWindowToTop => "BringWindowToTop",
+                Self::DestroyWindow => "DestroyWindow",
+                Self::SetWindowPos => "SetWindowPos",
+                Self::GdiFlush => "GdiFlush",
+                Self::GetDIBits => "GetDIBits",
+                Self::CreateDIBSection => "CreateDIBSection",
+                Self::SetTimer => "SetTimer",
+                Self::GetObjectW => "GetObjectW",
+                Self::LoadImage => "LoadImage",
                 Self::CreatePen => "CreatePen",
                 Self::CreateBrush => "CreateBrush",
                 Self::GetCursorPos => "GetCursorPos",
@@ -141,6 +273,91 @@ impl fmt::Display for Win32Function {
     }
 }
 
+/// Common Win32 error codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Win32ErrorCode {
+    Success,
+    FileNotFound,
+    AccessDenied,
+    InvalidHandle,
+    NotEnoughMemory,
+    OutOfMemory,
+    NotSupported,
+    InvalidParameter,
+    CallNotImplemented,
+    InsufficientBuffer,
+    ModuleNotFound,
+    ProcedureNotFound,
+    InvalidWindowHandle,
+    InvalidMenuHandle,
+    InvalidCursorHandle,
+    ClassAlreadyExists,
+    ClassDoesNotExist,
+    ClassHasWindows,
+    CannotFindWindowClass,
+    NotChildWindow,
+    Unknown(DWORD),
+}
+
+impl Win32ErrorCode {
+    /// Convert a raw error code into a `Win32ErrorCode`. Codes that are not covered by
+    /// this enum become `Unknown`.
+    #[inline]
+    pub fn from_code(code: DWORD) -> Self {
+        match code {
+            winerror::ERROR_SUCCESS => Self::Success,
+            winerror::ERROR_FILE_NOT_FOUND => Self::FileNotFound,
+            winerror::ERROR_ACCESS_DENIED => Self::AccessDenied,
+            winerror::ERROR_INVALID_HANDLE => Self::InvalidHandle,
+            winerror::ERROR_NOT_ENOUGH_MEMORY => Self::NotEnoughMemory,
+            winerror::ERROR_OUTOFMEMORY => Self::OutOfMemory,
+            winerror::ERROR_NOT_SUPPORTED => Self::NotSupported,
+            winerror::ERROR_INVALID_PARAMETER => Self::InvalidParameter,
+            winerror::ERROR_CALL_NOT_IMPLEMENTED => Self::CallNotImplemented,
+            winerror::ERROR_INSUFFICIENT_BUFFER => Self::InsufficientBuffer,
+            winerror::ERROR_MOD_NOT_FOUND => Self::ModuleNotFound,
+            winerror::ERROR_PROC_NOT_FOUND => Self::ProcedureNotFound,
+            winerror::ERROR_INVALID_WINDOW_HANDLE => Self::InvalidWindowHandle,
+            winerror::ERROR_INVALID_MENU_HANDLE => Self::InvalidMenuHandle,
+            winerror::ERROR_INVALID_CURSOR_HANDLE => Self::InvalidCursorHandle,
+            winerror::ERROR_CLASS_ALREADY_EXISTS => Self::ClassAlreadyExists,
+            winerror::ERROR_CLASS_DOES_NOT_EXIST => Self::ClassDoesNotExist,
+            winerror::ERROR_CLASS_HAS_WINDOWS => Self::ClassHasWindows,
+            winerror::ERROR_CANNOT_FIND_WND_CLASS => Self::CannotFindWindowClass,
+            winerror::ERROR_NOT_CHILD_WINDOW => Self::NotChildWindow,
+            code => Self::Unknown(code),
+        }
+    }
+
+    /// Get the raw error code.
+    #[inline]
+    pub fn code(self) -> DWORD {
+        match self {
+            Self::Success => winerror::ERROR_SUCCESS,
+            Self::FileNotFound => winerror::ERROR_FILE_NOT_FOUND,
+            Self::AccessDenied => winerror::ERROR_ACCESS_DENIED,
+            Self::InvalidHandle => winerror::ERROR_INVALID_HANDLE,
+            Self::NotEnoughMemory => winerror::ERROR_NOT_ENOUGH_MEMORY,
+            Self::OutOfMemory => winerror::ERROR_OUTOFMEMORY,
+            Self::NotSupported => winerror::ERROR_NOT_SUPPORTED,
+            Self::InvalidParameter => winerror::ERROR_INVALID_PARAMETER,
+            Self::CallNotImplemented => winerror::ERROR_CALL_NOT_IMPLEMENTED,
+            Self::InsufficientBuffer => winerror::ERROR_INSUFFICIENT_BUFFER,
+            Self::ModuleNotFound => winerror::ERROR_MOD_NOT_FOUND,
+            Self::ProcedureNotFound => winerror::ERROR_PROC_NOT_FOUND,
+            Self::InvalidWindowHandle => winerror::ERROR_INVALID_WINDOW_HANDLE,
+            Self::InvalidMenuHandle => winerror::ERROR_INVALID_MENU_HANDLE,
+            Self::InvalidCursorHandle => winerror::ERROR_INVALID_CURSOR_HANDLE,
+            Self::ClassAlreadyExists => winerror::ERROR_CLASS_ALREADY_EXISTS,
+            Self::ClassDoesNotExist => winerror::ERROR_CLASS_DOES_NOT_EXIST,
+            Self::ClassHasWindows => winerror::ERROR_CLASS_HAS_WINDOWS,
+            Self::CannotFindWindowClass => winerror::ERROR_CANNOT_FIND_WND_CLASS,
+            Self::NotChildWindow => winerror::ERROR_NOT_CHILD_WINDOW,
+            Self::Unknown(code) => code,
+        }
+    }
+}
+
 /// The error used by the Porcupine API.
 #[derive(Debug, Clone)]
 pub enum Error {
@@ -153,6 +370,11 @@ pub enum Error {
         function: Win32Function,
     },
     Utf8(FromUtf8Error),
+    /// A wide string contained invalid UTF-16 data.
+    Utf16,
+    /// An I/O error occurred.
+    #[cfg(feature = "std")]
+    Io(Arc<std::io::Error>),
     /// Attempted to upgrade a dead Weak pointer.
     ExpiredWeakPtr,
     NoGDIStorage,
@@ -171,6 +393,9 @@ impl fmt::Display for Error {
                 function,
             } => write!(f, "{} threw error code {}: {}", function, code, message),
             Error::Utf8(u) => fmt::Display::fmt(u, f),
+            Error::Utf16 => f.pad("Wide string contained invalid UTF-16 data"),
+            #[cfg(feature = "std")]
+            Error::Io(e) => fmt::Display::fmt(e, f),
             Error::ExpiredWeakPtr => f.pad("Attempted to upgrade a dead Weak pointer"),
             Error::NoGDIStorage => f.pad("No GDI storage was found in the device context"),
             Error::AlreadyHadGDIStorage => {
@@ -180,6 +405,17 @@ impl fmt::Display for Error {
     }
 }
 
+impl Error {
+    /// Get the kind of Win32 error code this error carries, if it is a Win32 error.
+    #[inline]
+    pub fn code_kind(&self) -> Option<Win32ErrorCode> {
+        match self {
+            Error::Win32 { code, .. } => Some(Win32ErrorCode::from_code(*code)),
+            _ => None,
+        }
+    }
+}
+
 impl From<Error> for fmt::Error {
     fn from(_f: Error) -> Self {
         Self
@@ -192,18 +428,40 @@ impl From<FromUtf8Error> for Error {
     }
 }
 
+impl From<FromUtf16Error> for Error {
+    fn from(_futf16: FromUtf16Error) -> Self {
+        Self::Utf16
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(io: std::io::Error) -> Self {
+        Self::Io(Arc::new(io))
+    }
+}
+
 /// A result, for conveinence.
 pub type Result<T> = core::result::Result<T, Error>;
 
 /// Get the last Win32 error, if applicable.
 pub fn win32_error(function: Win32Function) -> Error {
     let error = unsafe { errhandlingapi::GetLastError() };
+    error_from_code(error, function)
+}
+
+/// Get an error from an `HRESULT` returned by a Win32 function.
+pub fn hresult_error(hr: HRESULT, function: Win32Function) -> Error {
+    error_from_code(hr as DWORD, function)
+}
 
+// format an error code into an error
+fn error_from_code(error: DWORD, function: Win32Function) -> Error {
     const ERROR_BUFFER_SIZE: usize = 256;
-    let mut error_buffer = Vec::with_capacity(ERROR_BUFFER_SIZE);
+    let mut error_buffer: [WCHAR; ERROR_BUFFER_SIZE] = [0; ERROR_BUFFER_SIZE];
 
     let len = unsafe {
-        FormatMessageA(
+        FormatMessageW(
             FORMAT_MESSAGE_IGNORE_INSERTS
                 | FORMAT_MESSAGE_FROM_SYSTEM
                 | FORMAT_MESSAGE_ARGUMENT_ARRAY,
@@ -211,27 +469,24 @@ pub fn win32_error(function: Win32Function) -> Error {
             error,
             0,
             error_buffer.as_mut_ptr(),
-            (ERROR_BUFFER_SIZE + 1) as DWORD,
+            ERROR_BUFFER_SIZE as DWORD,
             ptr::null_mut(),
         )
-    };
-
-    if len == 0 {
-        return Error::Win32 {
-            code: error,
-            message: "No error message detected".to_string(),
-            function,
-        };
-    }
+    } as usize;
 
-    unsafe { error_buffer.set_len(len as usize) };
+    // FormatMessage writes a NUL terminator after the message
+    let message = match len {
+        0 => None,
+        len => error_buffer.get(..=len).and_then(WStr::from_bytes),
+    };
 
-    match String::from_utf8(error_buffer.into_iter().map(|i| i as u8).collect()) {
-        Ok(s) => Error::Win32 {
-            code: error,
-            message: s,
-            function,
+    Error::Win32 {
+        code: error,
+        message: match message {
+            // Windows ends its messages with a line break
+            Some(message) => message.into_string_lossy().trim_end().to_string(),
+            None => "No error message detected".to_string(),
         },
-        Err(e) => e.into(),
+        function,
     }
 }
@@ -0,0 +1,129 @@
+/* -----------------------------------------------------------------------------------
+ * src/font.rs - Font enumeration.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::DeviceContext;
+use alloc::{string::String, vec::Vec};
+use core::mem;
+use cty::c_int;
+use winapi::{
+    shared::minwindef::{DWORD, LPARAM},
+    um::wingdi::{self, ENUMLOGFONTEXW, LOGFONTW, TEXTMETRICW},
+};
+
+/// A font installed on the system.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FontEntry {
+    /// The face name of the font.
+    pub name: String,
+    /// Whether all of the font's characters have the same width.
+    pub fixed_pitch: bool,
+    /// The character set this entry was enumerated for.
+    pub charset: u8,
+}
+
+/// Get the fonts available on a device context. If a character set is given, only fonts
+/// that support it are returned. Otherwise, a font that supports several character sets
+/// is returned once for each of them. If no font matches, the list is empty.
+pub fn enumerate_fonts(dc: &DeviceContext, charset: Option<u8>) -> crate::Result<Vec<FontEntry>> {
+    let mut entries: Vec<FontEntry> = Vec::new();
+
+    // an empty face name and no pitch and family enumerates every font
+    let mut logfont: LOGFONTW = unsafe { mem::zeroed() };
+    logfont.lfCharSet = charset.unwrap_or(wingdi::DEFAULT_CHARSET as u8);
+
+    // this returns whatever the callback last returned, or zero if nothing matched, so
+    // there is no failure to report
+    unsafe {
+        wingdi::EnumFontFamiliesExW(
+            dc.hdc().as_mut(),
+            &mut logfont,
+            Some(font_enum_proc),
+            &mut entries as *mut Vec<FontEntry> as LPARAM,
+            0,
+        )
+    };
+
+    Ok(entries)
+}
+
+// collect each enumerated font into the vector passed through the lparam
+unsafe extern "system" fn font_enum_proc(
+    logfont: *const LOGFONTW,
+    _metrics: *const TEXTMETRICW,
+    _font_type: DWORD,
+    lparam: LPARAM,
+) -> c_int {
+    // for EnumFontFamiliesEx, the logfont is actually an ENUMLOGFONTEX
+    let logfont = &*(logfont as *const ENUMLOGFONTEXW);
+    let entries = &mut *(lparam as *mut Vec<FontEntry>);
+
+    let face_name = &logfont.elfLogFont.lfFaceName;
+    let len = face_name
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(face_name.len());
+
+    entries.push(FontEntry {
+        name: String::from_utf16_lossy(&face_name[..len]),
+        fixed_pitch: logfont.elfLogFont.lfPitchAndFamily & 0x3 == wingdi::FIXED_PITCH as u8,
+        charset: logfont.elfLogFont.lfCharSet,
+    });
+
+    // continue enumerating
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charset_without_fonts_is_empty() {
+        let dc = DeviceContext::information_dc().unwrap();
+        // no character set has this value, so no font can support it
+        let entries = enumerate_fonts(&dc, Some(0x10)).unwrap();
+        assert!(entries.is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+/* -----------------------------------------------------------------------------------
+ * src/handler.rs - Trait-based window procedures.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::{
+    mutexes::Mutex, DroplessWindow, ExtendedWindowStyle, GenericWindow, OwnedWindowClass, Window,
+    WindowStyle,
+};
+use alloc::{boxed::Box, collections::BTreeSet, string::ToString};
+use core::{any::Any, marker::PhantomData, mem, ptr};
+use cty::c_int;
+use euclid::default::Rect;
+use winapi::{
+    ctypes::c_void,
+    shared::{
+        basetsd::LONG_PTR,
+        minwindef::{FALSE, LOWORD, LPARAM, LRESULT, UINT, WPARAM},
+        windef::HWND,
+    },
+    um::winuser::{self, CREATESTRUCTA},
+};
+
+/// An object that handles the messages sent to a window.
+///
+/// Register a class for it with `register_handler_class`, and then create windows of
+/// that class with `create_handler_window`. The handler is dropped once its window is
+/// destroyed.
+///
+/// While one of these methods is running, any message sent to the window from inside of
+/// it (aside from the ones caused by closing the window) is passed straight to
+/// `DefWindowProc`, since the handler is already borrowed.
+pub trait WindowHandler: Any {
+    /// Called when the window needs to be painted. Any part of the window that is still
+    /// invalid afterwards is validated.
+    #[inline]
+    fn on_paint(&mut self, _window: &DroplessWindow) {}
+
+    /// Called when the user asks to close the window. If this returns true, which it does
+    /// by default, the window is destroyed.
+    #[inline]
+    fn on_close(&mut self, _window: &DroplessWindow) -> bool {
+        true
+    }
+
+    /// Called when the window is being destroyed. The main window of an application
+    /// usually calls `PostQuitMessage` here.
+    #[inline]
+    fn on_destroy(&mut self, _window: &DroplessWindow) {}
+
+    /// Called when a menu item, accelerator or control sends a command.
+    #[inline]
+    fn on_command(&mut self, _id: u16) {}
+
+    /// Called for any other message. If this returns `None`, which it does by default,
+    /// the message is passed to `DefWindowProc`.
+    #[inline]
+    fn on_message(
+        &mut self,
+        _window: &DroplessWindow,
+        _msg: UINT,
+        _wparam: WPARAM,
+        _lparam: LPARAM,
+    ) -> Option<LRESULT> {
+        None
+    }
+}
+
+/// A window class registered with `register_handler_class`, whose windows are driven by
+/// a `WindowHandler` of type `H`.
+pub struct HandlerClass<H> {
+    class: OwnedWindowClass,
+    _handler: PhantomData<fn() -> H>,
+}
+
+impl<H> HandlerClass<H> {
+    // get the class itself; windows of this class must only be created by
+    // create_handler_window, which passes the creation parameter the procedure expects
+    #[inline]
+    pub(crate) fn class(&self) -> &OwnedWindowClass {
+        &self.class
+    }
+}
+
+lazy_static::lazy_static! {
+    // the addresses of the handler slots that create_handler_window is currently passing
+    // to CreateWindowEx; the window procedure ignores any other creation parameter
+    static ref PENDING_HANDLERS: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+}
+
+/// Register a window class whose windows are driven by a `WindowHandler` of type `H`.
+pub fn register_handler_class<H: WindowHandler>(name: &str) -> crate::Result<HandlerClass<H>> {
+    let mut class = OwnedWindowClass::new(name.to_string());
+    class.set_window_proc(Some(handler_procedure::<H>));
+    class.register()?;
+    Ok(HandlerClass {
+        class,
+        _handler: PhantomData,
+    })
+}
+
+/// Create a window of a class registered with `register_handler_class::<H>`, which will
+/// be driven by `handler`.
+///
+/// The handler lives in the window's user data, so the user data methods of `Window`
+/// must not be used on the returned window.
+pub fn create_handler_window<H: WindowHandler>(
+    window_class: &HandlerClass<H>,
+    window_name: &str,
+    style: WindowStyle,
+    extended_style: ExtendedWindowStyle,
+    bounds: Rect<c_int>,
+    parent: Option<&Window>,
+    handler: H,
+) -> crate::Result<Window> {
+    // the window procedure takes the handler out of this slot during WM_NCCREATE; if
+    // creation fails before then, the handler is still here and is dropped normally
+    let mut slot = Some(handler);
+    let slot_ptr = &mut slot as *mut Option<H>;
+    PENDING_HANDLERS.lock().insert(slot_ptr as usize);
+
+    let result = unsafe {
+        Window::with_raw_creation_param(
+            window_class.class(),
+            window_name,
+            style,
+            extended_style,
+            bounds,
+            parent,
+            slot_ptr as *mut c_void,
+        )
+    };
+
+    PENDING_HANDLERS.lock().remove(&(slot_ptr as usize));
+    result
+}
+
+// the handler behind a window, stored in its user data
+struct HandlerState<H> {
+    handler: H,
+    // whether or not the handler is currently borrowed
+    running: bool,
+    // whether the window was destroyed while the handler was borrowed
+    destroyed: bool,
+}
+
+// what to do after the handler has returned
+enum Outcome {
+    Return(LRESULT),
+    Default,
+    Destroy,
+}
+
+unsafe extern "system" fn handler_procedure<H: WindowHandler>(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    // move the handler from the creation parameter into the window's user data
+    if msg == winuser::WM_NCCREATE {
+        let create_struct = &*(lparam as *const CREATESTRUCTA);
+        let slot = create_struct.lpCreateParams as *mut Option<H>;
+
+        // the class name is known, so a window of this class could have been created
+        // with some other creation parameter; only trust the slots we put there
+        if !PENDING_HANDLERS.lock().contains(&(slot as usize)) {
+            return FALSE as LRESULT;
+        }
+
+        let handler = match slot.as_mut().and_then(Option::take) {
+            Some(handler) => handler,
+            None => return FALSE as LRESULT,
+        };
+
+        let state = Box::into_raw(Box::new(HandlerState {
+            handler,
+            running: false,
+            destroyed: false,
+        }));
+        winuser::SetWindowLongPtrA(hwnd, winuser::GWLP_USERDATA, state as LONG_PTR);
+        return winuser::DefWindowProcA(hwnd, msg, wparam, lparam);
+    }
+
+    let state = winuser::GetWindowLongPtrA(hwnd, winuser::GWLP_USERDATA) as *mut HandlerState<H>;
+    if state.is_null() {
+        return winuser::DefWindowProcA(hwnd, msg, wparam, lparam);
+    }
+
+    // this is the last message the window receives, so drop the handler
+    if msg == winuser::WM_NCDESTROY {
+        winuser::SetWindowLongPtrA(hwnd, winuser::GWLP_USERDATA, 0);
+        if (*state).running {
+            (*state).destroyed = true;
+        } else {
+            mem::drop(Box::from_raw(state));
+        }
+        return winuser::DefWindowProcA(hwnd, msg, wparam, lparam);
+    }
+
+    if (*state).running {
+        return winuser::DefWindowProcA(hwnd, msg, wparam, lparam);
+    }
+
+    let window = DroplessWindow::new(hwnd);
+    (*state).running = true;
+    let handler = &mut (*state).handler;
+
+    let outcome = match msg {
+        winuser::WM_PAINT => {
+            handler.on_paint(&window);
+            winuser::ValidateRect(hwnd, ptr::null());
+            Outcome::Return(0)
+        }
+        winuser::WM_CLOSE => {
+            if handler.on_close(&window) {
+                Outcome::Destroy
+            } else {
+                Outcome::Return(0)
+            }
+        }
+        winuser::WM_DESTROY => {
+            handler.on_destroy(&window);
+            Outcome::Return(0)
+        }
+        winuser::WM_COMMAND => {
+            handler.on_command(LOWORD(wparam as u32));
+            Outcome::Return(0)
+        }
+        msg => match handler.on_message(&window, msg, wparam, lparam) {
+            Some(result) => Outcome::Return(result),
+            None => Outcome::Default,
+        },
+    };
+
+    (*state).running = false;
+    if (*state).destroyed {
+        mem::drop(Box::from_raw(state));
+    }
+
+    match outcome {
+        Outcome::Return(result) => result,
+        Outcome::Default => winuser::DefWindowProcA(hwnd, msg, wparam, lparam),
+        Outcome::Destroy => {
+            // the handler is no longer borrowed, so WM_DESTROY can reach it
+            let _ = window.destroy();
+            0
+        }
+    }
+}
@@ -0,0 +1,216 @@
+/* -----------------------------------------------------------------------------------
+ * src/hook.rs - Thread-local windows hooks
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+//! Hooks that watch messages and input on the current thread.
+
+use crate::mutexes::Mutex;
+use alloc::{boxed::Box, collections::BTreeMap};
+use core::{
+    marker::PhantomData,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+use cty::c_int;
+use winapi::{
+    shared::{
+        minwindef::{DWORD, LPARAM, LRESULT, WPARAM},
+        windef::HHOOK__,
+    },
+    um::{processthreadsapi, winuser},
+};
+
+/// The kinds of hooks that can be installed on the current thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(i32)]
+pub enum HookKind {
+    /// Called for keyboard messages, before they are retrieved by the message loop.
+    /// Returning `Some` with a non-zero value discards the keystroke.
+    Keyboard = winuser::WH_KEYBOARD,
+    /// Called for every message retrieved from the thread's message queue. The `lParam`
+    /// points to the `MSG`, which the hook can modify.
+    GetMessage = winuser::WH_GETMESSAGE,
+    /// Called for every message sent to a window procedure on this thread, before the
+    /// window procedure runs. The `lParam` points to a `CWPSTRUCT`.
+    CallWndProc = winuser::WH_CALLWNDPROC,
+}
+
+type HookCallbackFn = Box<dyn FnMut(c_int, WPARAM, LPARAM) -> Option<LRESULT>>;
+
+// a hook callback, stored while the hook is installed
+struct HookCallback(Option<HookCallbackFn>);
+
+// hook callbacks are only ever called from the thread that installed the hook
+unsafe impl Send for HookCallback {}
+
+lazy_static::lazy_static! {
+    static ref HOOK_CALLBACKS: Mutex<BTreeMap<(DWORD, HookKind), HookCallback>> =
+        Mutex::new(BTreeMap::new());
+}
+
+// run the callback for the current thread's hook of this kind
+unsafe fn hook_trampoline(kind: HookKind, code: c_int, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    // a negative code means that the hook must pass the message on without processing it
+    if code >= 0 {
+        let key = (processthreadsapi::GetCurrentThreadId(), kind);
+
+        // take the callback out of the table, so that messages sent from inside of it
+        // skip it instead of deadlocking
+        let callback = match HOOK_CALLBACKS.lock().get_mut(&key) {
+            Some(HookCallback(c)) => c.take(),
+            None => None,
+        };
+
+        if let Some(mut callback) = callback {
+            let result = callback(code, wparam, lparam);
+
+            // put it back, unless the hook was removed during the callback
+            if let Some(HookCallback(c)) = HOOK_CALLBACKS.lock().get_mut(&key) {
+                *c = Some(callback);
+            }
+
+            if let Some(result) = result {
+                return result;
+            }
+        }
+    }
+
+    // the hook handle is ignored by CallNextHookEx
+    winuser::CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+}
+
+unsafe extern "system" fn keyboard_trampoline(
+    code: c_int,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    hook_trampoline(HookKind::Keyboard, code, wparam, lparam)
+}
+
+unsafe extern "system" fn get_message_trampoline(
+    code: c_int,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    hook_trampoline(HookKind::GetMessage, code, wparam, lparam)
+}
+
+unsafe extern "system" fn call_wnd_proc_trampoline(
+    code: c_int,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    hook_trampoline(HookKind::CallWndProc, code, wparam, lparam)
+}
+
+/// A hook installed on the current thread. The hook is removed and its closure dropped
+/// when this is dropped.
+#[derive(Debug)]
+pub struct Hook {
+    hhook: AtomicPtr<HHOOK__>,
+    key: (DWORD, HookKind),
+    // the closure may not be Send, so the hook has to be dropped on its own thread
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl Hook {
+    /// Install a hook of the given kind on the current thread. The closure is given the
+    /// hook code, `wParam` and `lParam`. If it returns `Some`, that value is returned to
+    /// Windows; if it returns `None`, the call is passed on to the next hook.
+    ///
+    /// Only one hook of each kind can be installed on a thread at a time through this
+    /// type. Hooks are never global, so no DLL is needed.
+    pub fn new<F>(kind: HookKind, callback: F) -> crate::Result<Self>
+    where
+        F: FnMut(c_int, WPARAM, LPARAM) -> Option<LRESULT> + 'static,
+    {
+        let thread_id = unsafe { processthreadsapi::GetCurrentThreadId() };
+        let key = (thread_id, kind);
+
+        {
+            let mut callbacks = HOOK_CALLBACKS.lock();
+            if callbacks.contains_key(&key) {
+                return Err(crate::Error::StaticMsg(
+                    "A hook of this kind is already installed on this thread",
+                ));
+            }
+            callbacks.insert(key, HookCallback(Some(Box::new(callback))));
+        }
+
+        let trampoline = match kind {
+            HookKind::Keyboard => keyboard_trampoline,
+            HookKind::GetMessage => get_message_trampoline,
+            HookKind::CallWndProc => call_wnd_proc_trampoline,
+        };
+
+        let hhook = unsafe {
+            winuser::SetWindowsHookExW(kind as c_int, Some(trampoline), ptr::null_mut(), thread_id)
+        };
+
+        if hhook.is_null() {
+            let err = crate::win32_error(crate::Win32Function::SetWindowsHookExW);
+            HOOK_CALLBACKS.lock().remove(&key);
+            Err(err)
+        } else {
+            Ok(Self {
+                hhook: AtomicPtr::new(hhook),
+                key,
+                _thread_bound: PhantomData,
+            })
+        }
+    }
+
+    /// Get the kind of this hook.
+    #[inline]
+    pub fn kind(&self) -> HookKind {
+        self.key.1
+    }
+}
+
+impl Drop for Hook {
+    fn drop(&mut self) {
+        unsafe { winuser::UnhookWindowsHookEx(self.hhook.load(Ordering::Acquire)) };
+        HOOK_CALLBACKS.lock().remove(&self.key);
+    }
+}
@@ -0,0 +1,247 @@
+/* -----------------------------------------------------------------------------------
+ * src/input.rs - Synthetic keyboard and mouse input.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::VirtualKey;
+use alloc::vec::Vec;
+use core::mem;
+use cty::c_int;
+use winapi::{
+    shared::minwindef::{DWORD, UINT, WORD},
+    um::winuser::{self, INPUT, KEYBDINPUT, MOUSEINPUT},
+};
+
+bitflags::bitflags! {
+    #[doc = "Flags describing a synthetic keystroke"]
+    pub struct KeyEventFlags : DWORD {
+        const NONE = 0;
+        const EXTENDED_KEY = winuser::KEYEVENTF_EXTENDEDKEY;
+        const KEY_UP = winuser::KEYEVENTF_KEYUP;
+        const SCAN_CODE = winuser::KEYEVENTF_SCANCODE;
+        const UNICODE = winuser::KEYEVENTF_UNICODE;
+    }
+}
+
+bitflags::bitflags! {
+    #[doc = "Flags describing a synthetic mouse event"]
+    pub struct MouseEventFlags : DWORD {
+        const NONE = 0;
+        const MOVE = winuser::MOUSEEVENTF_MOVE;
+        const ABSOLUTE = winuser::MOUSEEVENTF_ABSOLUTE;
+        const VIRTUAL_DESK = winuser::MOUSEEVENTF_VIRTUALDESK;
+        const LEFT_DOWN = winuser::MOUSEEVENTF_LEFTDOWN;
+        const LEFT_UP = winuser::MOUSEEVENTF_LEFTUP;
+        const RIGHT_DOWN = winuser::MOUSEEVENTF_RIGHTDOWN;
+        const RIGHT_UP = winuser::MOUSEEVENTF_RIGHTUP;
+        const MIDDLE_DOWN = winuser::MOUSEEVENTF_MIDDLEDOWN;
+        const MIDDLE_UP = winuser::MOUSEEVENTF_MIDDLEUP;
+        const WHEEL = winuser::MOUSEEVENTF_WHEEL;
+        const HORIZONTAL_WHEEL = winuser::MOUSEEVENTF_HWHEEL;
+    }
+}
+
+/// A synthetic keystroke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+    /// The key that is pressed or released. This is ignored for `UNICODE` events.
+    pub key: Option<VirtualKey>,
+    /// The hardware scan code of the key, or the UTF-16 code unit for `UNICODE` events.
+    pub scan_code: u16,
+    pub flags: KeyEventFlags,
+}
+
+impl KeyEvent {
+    /// Press a key.
+    #[inline]
+    pub fn down(key: VirtualKey) -> Self {
+        Self {
+            key: Some(key),
+            scan_code: 0,
+            flags: KeyEventFlags::NONE,
+        }
+    }
+
+    /// Release a key.
+    #[inline]
+    pub fn up(key: VirtualKey) -> Self {
+        Self {
+            key: Some(key),
+            scan_code: 0,
+            flags: KeyEventFlags::KEY_UP,
+        }
+    }
+
+    /// Type a UTF-16 code unit, regardless of the keyboard layout. Characters outside of
+    /// the Basic Multilingual Plane take two events, one for each surrogate.
+    #[inline]
+    pub fn unicode(unit: u16, up: bool) -> Self {
+        Self {
+            key: None,
+            scan_code: unit,
+            flags: if up {
+                KeyEventFlags::UNICODE | KeyEventFlags::KEY_UP
+            } else {
+                KeyEventFlags::UNICODE
+            },
+        }
+    }
+}
+
+/// A synthetic mouse event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MouseEvent {
+    /// The horizontal motion. With `ABSOLUTE`, this is a normalized coordinate between 0
+    /// and 65535; otherwise, it is relative to the last position.
+    pub dx: c_int,
+    /// The vertical motion, in the same form as `dx`.
+    pub dy: c_int,
+    /// The wheel movement for `WHEEL` and `HORIZONTAL_WHEEL` events, in multiples of
+    /// `WHEEL_DELTA`.
+    pub mouse_data: DWORD,
+    pub flags: MouseEventFlags,
+}
+
+impl MouseEvent {
+    /// Move the mouse relative to its current position.
+    #[inline]
+    pub fn move_by(dx: c_int, dy: c_int) -> Self {
+        Self {
+            dx,
+            dy,
+            mouse_data: 0,
+            flags: MouseEventFlags::MOVE,
+        }
+    }
+
+    /// Move the mouse to a normalized point on the primary monitor, where (0, 0) is the
+    /// top left corner and (65535, 65535) is the bottom right corner.
+    #[inline]
+    pub fn move_to(x: c_int, y: c_int) -> Self {
+        Self {
+            dx: x,
+            dy: y,
+            mouse_data: 0,
+            flags: MouseEventFlags::MOVE | MouseEventFlags::ABSOLUTE,
+        }
+    }
+
+    /// Press or release buttons without moving the mouse.
+    #[inline]
+    pub fn buttons(flags: MouseEventFlags) -> Self {
+        Self {
+            dx: 0,
+            dy: 0,
+            mouse_data: 0,
+            flags,
+        }
+    }
+}
+
+// send a set of inputs to the system
+fn send_input(mut inputs: Vec<INPUT>) -> crate::Result<()> {
+    let sent = unsafe {
+        winuser::SendInput(
+            inputs.len() as UINT,
+            inputs.as_mut_ptr(),
+            mem::size_of::<INPUT>() as c_int,
+        )
+    };
+
+    // if input was blocked by another thread, fewer events than requested are sent
+    if (sent as usize) < inputs.len() {
+        Err(crate::win32_error(crate::Win32Function::SendInput))
+    } else {
+        Ok(())
+    }
+}
+
+/// Inject a series of keystrokes into the input stream.
+pub fn send_keyboard_input(events: &[KeyEvent]) -> crate::Result<()> {
+    send_input(
+        events
+            .iter()
+            .map(|event| {
+                let mut input = INPUT {
+                    type_: winuser::INPUT_KEYBOARD,
+                    u: unsafe { mem::zeroed() },
+                };
+                *unsafe { input.u.ki_mut() } = KEYBDINPUT {
+                    wVk: match event.key {
+                        Some(key) if !event.flags.contains(KeyEventFlags::UNICODE) => key as WORD,
+                        _ => 0,
+                    },
+                    wScan: event.scan_code,
+                    dwFlags: event.flags.bits(),
+                    time: 0,
+                    dwExtraInfo: 0,
+                };
+                input
+            })
+            .collect(),
+    )
+}
+
+/// Inject a series of mouse events into the input stream.
+pub fn send_mouse_input(events: &[MouseEvent]) -> crate::Result<()> {
+    send_input(
+        events
+            .iter()
+            .map(|event| {
+                let mut input = INPUT {
+                    type_: winuser::INPUT_MOUSE,
+                    u: unsafe { mem::zeroed() },
+                };
+                *unsafe { input.u.mi_mut() } = MOUSEINPUT {
+                    dx: event.dx,
+                    dy: event.dy,
+                    mouseData: event.mouse_data,
+                    dwFlags: event.flags.bits(),
+                    time: 0,
+                    dwExtraInfo: 0,
+                };
+                input
+            })
+            .collect(),
+    )
+}
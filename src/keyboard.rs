@@ -0,0 +1,275 @@
+/* -----------------------------------------------------------------------------------
+ * src/keyboard.rs - Keyboard-related types and functions.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::mutexes::Mutex;
+use alloc::{string::String, vec::Vec};
+use core::{ptr::NonNull, sync::atomic::AtomicPtr};
+use cty::c_int;
+use winapi::{
+    shared::{
+        minwindef::{BYTE, UINT, WORD},
+        ntdef::LONG,
+        windef::HACCEL__,
+    },
+    um::winuser::{self, ACCEL},
+};
+
+/// A virtual key code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum VirtualKey {
+    Back = winuser::VK_BACK,
+    Tab = winuser::VK_TAB,
+    Return = winuser::VK_RETURN,
+    Shift = winuser::VK_SHIFT,
+    Control = winuser::VK_CONTROL,
+    Alt = winuser::VK_MENU,
+    Pause = winuser::VK_PAUSE,
+    CapsLock = winuser::VK_CAPITAL,
+    Escape = winuser::VK_ESCAPE,
+    Space = winuser::VK_SPACE,
+    PageUp = winuser::VK_PRIOR,
+    PageDown = winuser::VK_NEXT,
+    End = winuser::VK_END,
+    Home = winuser::VK_HOME,
+    Left = winuser::VK_LEFT,
+    Up = winuser::VK_UP,
+    Right = winuser::VK_RIGHT,
+    Down = winuser::VK_DOWN,
+    PrintScreen = winuser::VK_SNAPSHOT,
+    Insert = winuser::VK_INSERT,
+    Delete = winuser::VK_DELETE,
+    Key0 = 0x30,
+    Key1 = 0x31,
+    Key2 = 0x32,
+    Key3 = 0x33,
+    Key4 = 0x34,
+    Key5 = 0x35,
+    Key6 = 0x36,
+    Key7 = 0x37,
+    Key8 = 0x38,
+    Key9 = 0x39,
+    A = 0x41,
+    B = 0x42,
+    C = 0x43,
+    D = 0x44,
+    E = 0x45,
+    F = 0x46,
+    G = 0x47,
+    H = 0x48,
+    I = 0x49,
+    J = 0x4A,
+    K = 0x4B,
+    L = 0x4C,
+    M = 0x4D,
+    N = 0x4E,
+    O = 0x4F,
+    P = 0x50,
+    Q = 0x51,
+    R = 0x52,
+    S = 0x53,
+    T = 0x54,
+    U = 0x55,
+    V = 0x56,
+    W = 0x57,
+    X = 0x58,
+    Y = 0x59,
+    Z = 0x5A,
+    LeftWindows = winuser::VK_LWIN,
+    RightWindows = winuser::VK_RWIN,
+    Apps = winuser::VK_APPS,
+    Numpad0 = winuser::VK_NUMPAD0,
+    Numpad1 = winuser::VK_NUMPAD1,
+    Numpad2 = winuser::VK_NUMPAD2,
+    Numpad3 = winuser::VK_NUMPAD3,
+    Numpad4 = winuser::VK_NUMPAD4,
+    Numpad5 = winuser::VK_NUMPAD5,
+    Numpad6 = winuser::VK_NUMPAD6,
+    Numpad7 = winuser::VK_NUMPAD7,
+    Numpad8 = winuser::VK_NUMPAD8,
+    Numpad9 = winuser::VK_NUMPAD9,
+    Multiply = winuser::VK_MULTIPLY,
+    Add = winuser::VK_ADD,
+    Subtract = winuser::VK_SUBTRACT,
+    Decimal = winuser::VK_DECIMAL,
+    Divide = winuser::VK_DIVIDE,
+    F1 = winuser::VK_F1,
+    F2 = winuser::VK_F2,
+    F3 = winuser::VK_F3,
+    F4 = winuser::VK_F4,
+    F5 = winuser::VK_F5,
+    F6 = winuser::VK_F6,
+    F7 = winuser::VK_F7,
+    F8 = winuser::VK_F8,
+    F9 = winuser::VK_F9,
+    F10 = winuser::VK_F10,
+    F11 = winuser::VK_F11,
+    F12 = winuser::VK_F12,
+    NumLock = winuser::VK_NUMLOCK,
+    ScrollLock = winuser::VK_SCROLL,
+}
+
+impl VirtualKey {
+    /// Whether this key is on the extended part of the keyboard. The scan codes of these
+    /// keys are shared with other keys, and they are told apart by the extended flag.
+    #[inline]
+    pub fn is_extended(self) -> bool {
+        matches!(
+            self,
+            Self::PageUp
+                | Self::PageDown
+                | Self::End
+                | Self::Home
+                | Self::Left
+                | Self::Up
+                | Self::Right
+                | Self::Down
+                | Self::PrintScreen
+                | Self::Insert
+                | Self::Delete
+                | Self::LeftWindows
+                | Self::RightWindows
+                | Self::Apps
+                | Self::Divide
+                | Self::NumLock
+        )
+    }
+
+    /// Get the scan code for this key under the current keyboard layout.
+    #[inline]
+    pub fn scan_code(self) -> u32 {
+        unsafe { winuser::MapVirtualKeyW(self as UINT, winuser::MAPVK_VK_TO_VSC) }
+    }
+
+    /// Get the name of this key under the current keyboard layout, in the user's
+    /// language. Returns an empty string if the key has no name.
+    #[inline]
+    pub fn display_name(&self) -> String {
+        key_name(self.scan_code(), self.is_extended()).unwrap_or_default()
+    }
+}
+
+/// Get the name of the key with the given scan code, in the user's language.
+pub fn key_name(scan_code: u32, extended: bool) -> crate::Result<String> {
+    const KEY_NAME_BUFFER_SIZE: usize = 64;
+    let mut buffer = [0u16; KEY_NAME_BUFFER_SIZE];
+
+    // the lparam of a keystroke message, with the scan code in bits 16 to 23 and the
+    // extended flag in bit 24
+    let lparam = (((scan_code & 0xFF) << 16) | if extended { 1 << 24 } else { 0 }) as LONG;
+
+    let len =
+        unsafe { winuser::GetKeyNameTextW(lparam, buffer.as_mut_ptr(), KEY_NAME_BUFFER_SIZE as _) };
+    if len == 0 {
+        Err(crate::win32_error(crate::Win32Function::GetKeyNameTextW))
+    } else {
+        Ok(String::from_utf16(&buffer[..len as usize])?)
+    }
+}
+
+bitflags::bitflags! {
+    #[doc = "Modifier keys that must be held down for an accelerator to fire"]
+    pub struct AcceleratorModifiers : BYTE {
+        const NONE = 0;
+        const ALT = winuser::FALT;
+        const CONTROL = winuser::FCONTROL;
+        const SHIFT = winuser::FSHIFT;
+    }
+}
+
+/// A table of keyboard shortcuts that are translated into `WM_COMMAND` messages.
+///
+/// Pass it to `translate_accelerator` in the message loop to activate it.
+#[repr(transparent)]
+pub struct AcceleratorTable {
+    haccel: Mutex<AtomicPtr<HACCEL__>>,
+}
+
+impl AcceleratorTable {
+    /// Create a new accelerator table from a set of modifiers, virtual keys and the
+    /// command IDs they send to the window.
+    pub fn new(accelerators: &[(AcceleratorModifiers, VirtualKey, u16)]) -> crate::Result<Self> {
+        let mut accels: Vec<ACCEL> = accelerators
+            .iter()
+            .map(|(modifiers, key, command)| ACCEL {
+                fVirt: winuser::FVIRTKEY | modifiers.bits(),
+                key: *key as WORD,
+                cmd: *command,
+            })
+            .collect();
+
+        let haccel =
+            unsafe { winuser::CreateAcceleratorTableW(accels.as_mut_ptr(), accels.len() as c_int) };
+        if haccel.is_null() {
+            Err(crate::win32_error(
+                crate::Win32Function::CreateAcceleratorTableW,
+            ))
+        } else {
+            Ok(Self {
+                haccel: Mutex::new(AtomicPtr::new(haccel)),
+            })
+        }
+    }
+
+    /// Get the handle to this accelerator table.
+    ///
+    /// # Safety
+    ///
+    /// This function copies the pointer out of an AtomicPtr and is thus unsound.
+    #[inline]
+    pub unsafe fn haccel(&self) -> NonNull<HACCEL__> {
+        let mut p = self.haccel.lock();
+        let ptr = p.get_mut();
+        debug_assert!(!ptr.is_null());
+        NonNull::new_unchecked(*ptr)
+    }
+}
+
+impl Drop for AcceleratorTable {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { winuser::DestroyAcceleratorTable(*self.haccel.lock().get_mut()) };
+    }
+}
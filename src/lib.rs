@@ -68,22 +68,54 @@ pub use winapi::{
 };
 
 pub mod bitmap;
+pub mod buffered;
+pub mod color;
 pub mod commctrl;
+pub mod cursor;
 pub mod dc;
 pub mod draw;
+pub mod draw_list;
 mod error;
+pub mod font;
+pub mod handler;
+pub mod hook;
+pub mod input;
+pub mod keyboard;
+pub mod menu;
 pub mod module;
 pub mod msg;
+#[cfg(feature = "raw-window-handle")]
+mod raw_handle;
+pub mod region;
+pub mod timer;
+#[cfg(feature = "uxtheme")]
+pub mod uxtheme;
 pub mod window;
+pub mod wstr;
 
 pub use bitmap::*;
+pub use buffered::*;
+pub use color::*;
 pub use commctrl::*;
+pub use cursor::*;
 pub use dc::*;
 pub use draw::*;
+pub use draw_list::*;
 pub use error::*;
+pub use font::*;
+pub use handler::*;
+pub use hook::*;
+pub use input::*;
+pub use keyboard::*;
+pub use menu::*;
 pub use module::*;
 pub use msg::*;
+pub use region::*;
+pub use timer::*;
+#[cfg(feature = "uxtheme")]
+pub use uxtheme::*;
 pub use window::*;
+pub use wstr::*;
 
 // which mutexes do we use?
 pub(crate) mod mutexes {
@@ -104,6 +136,24 @@ pub fn wboolify(rbool: bool) -> winapi::shared::minwindef::BOOL {
     }
 }
 
+// delete a GDI object. With the gdi-debug feature, failures are reported, since the
+// most common reason for one is that the object is still selected into a DC
+#[inline]
+pub(crate) unsafe fn delete_gdi_object(object: *mut winapi::ctypes::c_void, kind: &'static str) {
+    let _res = winapi::um::wingdi::DeleteObject(object);
+
+    #[cfg(feature = "gdi-debug")]
+    if _res == 0 {
+        std::eprintln!(
+            "porcupine: failed to delete a {} at {:p}; is it still selected into a device context?",
+            kind,
+            object
+        );
+    }
+    #[cfg(not(feature = "gdi-debug"))]
+    let _ = kind;
+}
+
 /// Utility function to convert a Euclid rect to a Windows rect.
 #[inline]
 pub fn eurect_to_winrect(
@@ -117,6 +167,19 @@ pub fn eurect_to_winrect(
     }
 }
 
+/// Utility function to convert a Windows rect to a Euclid rect.
+#[inline]
+pub fn winrect_to_eurect(
+    winrect: winapi::shared::windef::RECT,
+) -> euclid::default::Rect<cty::c_int> {
+    euclid::rect(
+        winrect.left,
+        winrect.top,
+        winrect.right - winrect.left,
+        winrect.bottom - winrect.top,
+    )
+}
+
 use cty::c_int;
 use euclid::default::Point2D;
 use maybe_uninit::MaybeUninit;
@@ -134,6 +197,40 @@ pub fn cursor_pos() -> Result<Point2D<c_int>> {
     }
 }
 
+/// Move the mouse cursor to a location on screen.
+#[inline]
+pub fn set_cursor_pos(pt: Point2D<c_int>) -> Result<()> {
+    if unsafe { winuser::SetCursorPos(pt.x, pt.y) } == 0 {
+        Err(win32_error(Win32Function::SetCursorPos))
+    } else {
+        Ok(())
+    }
+}
+
+/// Confine the mouse cursor to a rectangle on screen. Passing `None` releases the
+/// cursor so it can move anywhere again.
+#[inline]
+pub fn clip_cursor(rect: Option<euclid::default::Rect<c_int>>) -> Result<()> {
+    let rect = rect.map(eurect_to_winrect);
+    let rect_ptr = match rect {
+        Some(ref rect) => rect as *const _,
+        None => core::ptr::null(),
+    };
+
+    if unsafe { winuser::ClipCursor(rect_ptr) } == 0 {
+        Err(win32_error(Win32Function::ClipCursor))
+    } else {
+        Ok(())
+    }
+}
+
+/// Show or hide the mouse cursor. Windows keeps a display counter; the cursor is shown
+/// while it is non-negative. This returns the new value of the counter.
+#[inline]
+pub fn show_cursor(show: bool) -> i32 {
+    unsafe { winuser::ShowCursor(wboolify(show)) }
+}
+
 pub mod prelude {
     pub use super::GenericWindow;
 }
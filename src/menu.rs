@@ -0,0 +1,272 @@
+/* -----------------------------------------------------------------------------------
+ * src/menu.rs - A wrapper around Win32 menus.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::{mutexes::Mutex, WString};
+use core::{
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::Deref,
+    ptr::{self, NonNull},
+    sync::atomic::AtomicPtr,
+};
+use cty::c_int;
+use winapi::{
+    shared::{
+        basetsd::UINT_PTR,
+        minwindef::UINT,
+        windef::{HMENU, HMENU__},
+    },
+    um::winuser,
+};
+
+/// The state of a menu item, as returned by the closure passed to `Menu::update_items`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemState {
+    pub enabled: bool,
+    pub checked: bool,
+}
+
+impl Default for ItemState {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            checked: false,
+        }
+    }
+}
+
+/// A menu bar, or a popup menu.
+pub struct Menu {
+    hmenu: Mutex<AtomicPtr<HMENU__>>,
+}
+
+impl Menu {
+    // wrap a new menu handle
+    fn from_hmenu(hmenu: HMENU, function: crate::Win32Function) -> crate::Result<Self> {
+        if hmenu.is_null() {
+            Err(crate::win32_error(function))
+        } else {
+            Ok(Self {
+                hmenu: Mutex::new(AtomicPtr::new(hmenu)),
+            })
+        }
+    }
+
+    /// Create a new, empty menu bar.
+    #[inline]
+    pub fn new() -> crate::Result<Self> {
+        Self::from_hmenu(
+            unsafe { winuser::CreateMenu() },
+            crate::Win32Function::CreateMenu,
+        )
+    }
+
+    /// Create a new, empty popup menu. Popup menus can be used as submenus or context
+    /// menus.
+    #[inline]
+    pub fn popup() -> crate::Result<Self> {
+        Self::from_hmenu(
+            unsafe { winuser::CreatePopupMenu() },
+            crate::Win32Function::CreatePopupMenu,
+        )
+    }
+
+    // call AppendMenuW on this menu
+    fn append(&mut self, flags: UINT, id: UINT_PTR, text: Option<&str>) -> crate::Result<()> {
+        let text = text.map(WString::from);
+        if unsafe {
+            winuser::AppendMenuW(
+                self.hmenu().as_mut(),
+                flags,
+                id,
+                match text {
+                    Some(ref t) => t.as_ptr(),
+                    None => ptr::null(),
+                },
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::AppendMenuW))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Append an item with the given command ID and text to this menu. The ID is sent to
+    /// the window in a `WM_COMMAND` message when the item is clicked.
+    #[inline]
+    pub fn append_string(&mut self, id: u16, text: &str) -> crate::Result<()> {
+        self.append(winuser::MF_STRING, id as UINT_PTR, Some(text))
+    }
+
+    /// Append a submenu to this menu. The submenu is destroyed along with this menu.
+    #[inline]
+    pub fn append_submenu(&mut self, submenu: Menu, text: &str) -> crate::Result<()> {
+        let hsubmenu = unsafe { submenu.hmenu() };
+        self.append(
+            winuser::MF_STRING | winuser::MF_POPUP,
+            hsubmenu.as_ptr() as UINT_PTR,
+            Some(text),
+        )?;
+
+        // this menu now owns the submenu
+        mem::forget(submenu);
+        Ok(())
+    }
+
+    /// Append a separator line to this menu.
+    #[inline]
+    pub fn append_separator(&mut self) -> crate::Result<()> {
+        self.append(winuser::MF_SEPARATOR, 0, None)
+    }
+
+    /// Get the number of items in this menu.
+    #[inline]
+    pub fn item_count(&self) -> crate::Result<u32> {
+        match unsafe { winuser::GetMenuItemCount(self.hmenu().as_mut()) } {
+            -1 => Err(crate::win32_error(crate::Win32Function::GetMenuItemCount)),
+            count => Ok(count as u32),
+        }
+    }
+
+    /// Get the command ID of the item at a position in this menu. Returns `None` if
+    /// there is no item there, or if the item opens a submenu.
+    #[inline]
+    pub fn item_id(&self, pos: u32) -> Option<u16> {
+        match unsafe { winuser::GetMenuItemID(self.hmenu().as_mut(), pos as c_int) } {
+            UINT::MAX => None,
+            id => Some(id as u16),
+        }
+    }
+
+    /// Update whether each command item in this menu is enabled and checked, based on
+    /// its command ID. This is usually called in response to `WM_INITMENUPOPUP`, just
+    /// before the menu is shown. Submenus are not updated.
+    pub fn update_items<F: Fn(u16) -> ItemState>(&self, f: F) -> crate::Result<()> {
+        let mut hmenu = unsafe { self.hmenu() };
+
+        for pos in 0..self.item_count()? {
+            let state = match self.item_id(pos) {
+                Some(id) => f(id),
+                None => continue,
+            };
+
+            unsafe {
+                winuser::EnableMenuItem(
+                    hmenu.as_mut(),
+                    pos,
+                    winuser::MF_BYPOSITION
+                        | if state.enabled {
+                            winuser::MF_ENABLED
+                        } else {
+                            winuser::MF_GRAYED
+                        },
+                );
+                winuser::CheckMenuItem(
+                    hmenu.as_mut(),
+                    pos,
+                    winuser::MF_BYPOSITION
+                        | if state.checked {
+                            winuser::MF_CHECKED
+                        } else {
+                            winuser::MF_UNCHECKED
+                        },
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the handle to this menu.
+    ///
+    /// # Safety
+    ///
+    /// This function copies the pointer out of an AtomicPtr and is thus unsound.
+    #[inline]
+    pub unsafe fn hmenu(&self) -> NonNull<HMENU__> {
+        let mut p = self.hmenu.lock();
+        let ptr = p.get_mut();
+        debug_assert!(!ptr.is_null());
+        NonNull::new_unchecked(*ptr)
+    }
+}
+
+impl Drop for Menu {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { winuser::DestroyMenu(*self.hmenu.lock().get_mut()) };
+    }
+}
+
+/// A menu that belongs to something else, such as the menu bar of a window. It is not
+/// destroyed when dropped, and can't be given away like an owned `Menu`.
+pub struct MenuRef<'a> {
+    menu: ManuallyDrop<Menu>,
+    _owner: PhantomData<&'a ()>,
+}
+
+impl<'a> MenuRef<'a> {
+    // wrap a menu that belongs to something else
+    #[inline]
+    pub(crate) fn new(hmenu: NonNull<HMENU__>) -> Self {
+        Self {
+            menu: ManuallyDrop::new(Menu {
+                hmenu: Mutex::new(AtomicPtr::new(hmenu.as_ptr())),
+            }),
+            _owner: PhantomData,
+        }
+    }
+}
+
+impl<'a> Deref for MenuRef<'a> {
+    type Target = Menu;
+
+    #[inline]
+    fn deref(&self) -> &Menu {
+        &self.menu
+    }
+}
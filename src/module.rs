@@ -47,7 +47,7 @@ use crate::mutexes::Mutex;
 use core::{
     fmt,
     ptr::{self, NonNull},
-    sync::atomic::AtomicPtr,
+    sync::atomic::{AtomicPtr, Ordering},
 };
 use winapi::{
     shared::minwindef::{HINSTANCE__, HMODULE},
@@ -55,6 +55,10 @@ use winapi::{
 };
 
 /// Module-specific information.
+///
+/// The handle is that of the executable that started the process. It is obtained
+/// without increasing the module's reference count, and the executable stays loaded for
+/// as long as the process runs, so the handle is never freed.
 pub struct ModuleInfo {
     handle: AtomicPtr<HINSTANCE__>,
 }
@@ -69,10 +73,17 @@ impl ModuleInfo {
     /// Create a new instance of module information.
     #[inline]
     pub fn new() -> crate::Result<ModuleInfo> {
-        // get the module handle
+        // get the module handle, without taking a reference to the module
         let mut handle: HMODULE = ptr::null_mut();
 
-        if unsafe { libloaderapi::GetModuleHandleExA(0, ptr::null(), &mut handle) } == 0 {
+        if unsafe {
+            libloaderapi::GetModuleHandleExA(
+                libloaderapi::GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                ptr::null(),
+                &mut handle,
+            )
+        } == 0
+        {
             Err(crate::win32_error(crate::Win32Function::GetModuleHandleExA))
         } else {
             debug_assert!(!handle.is_null());
@@ -89,13 +100,26 @@ impl ModuleInfo {
     }
 }
 
-impl Drop for ModuleInfo {
-    fn drop(&mut self) {
-        unsafe { libloaderapi::FreeLibrary(*self.handle.get_mut()) };
-    }
-}
-
 lazy_static::lazy_static! {
     pub static ref MODULE_INFO: Mutex<ModuleInfo> =
         Mutex::new(ModuleInfo::new().expect("Unable to create module info"));
 }
+
+// the module handle never changes once it's been fetched, so it's cached here so that it
+// can be read without locking MODULE_INFO
+static INSTANCE: AtomicPtr<HINSTANCE__> = AtomicPtr::new(ptr::null_mut());
+
+/// Get the handle to the current module. Once it has been fetched, this does not lock
+/// `MODULE_INFO`.
+#[inline]
+pub fn module_instance() -> NonNull<HINSTANCE__> {
+    let instance = INSTANCE.load(Ordering::Acquire);
+    match NonNull::new(instance) {
+        Some(instance) => instance,
+        None => {
+            let instance = MODULE_INFO.lock().handle();
+            INSTANCE.store(instance.as_ptr(), Ordering::Release);
+            instance
+        }
+    }
+}
@@ -44,10 +44,17 @@
  */
 
 // just re-export MSG
-use core::{cmp::Ordering, ptr};
+use crate::{AcceleratorTable, DroplessWindow, GenericWindow};
+use alloc::vec::Vec;
+use core::{cmp::Ordering, ptr, slice};
+use cty::c_int;
+use euclid::default::{Point2D, Size2D};
 use maybe_uninit::MaybeUninit;
-use winapi::um::winuser;
 pub use winapi::um::winuser::MSG;
+use winapi::{
+    shared::minwindef::{FALSE, HIWORD, LOWORD, LPARAM, LRESULT, UINT, WPARAM},
+    um::winuser::{self, COPYDATASTRUCT},
+};
 
 /// Get a message from the Win32 event loop.
 #[inline]
@@ -70,6 +77,34 @@ pub fn get_message() -> crate::Result<Option<MSG>> {
     }
 }
 
+/// Get a message from the Win32 event loop without waiting for one. Returns `None` if
+/// there are no messages in the queue. If `remove` is true, the message is removed from
+/// the queue, just like with `get_message`.
+#[inline]
+pub fn peek_message(remove: bool) -> crate::Result<Option<MSG>> {
+    let mut m: MaybeUninit<MSG> = MaybeUninit::uninit();
+
+    // note: PeekMessage does not fail; zero means that the queue is empty
+    if unsafe {
+        winuser::PeekMessageA(
+            m.as_mut_ptr(),
+            ptr::null_mut(),
+            0,
+            0,
+            if remove {
+                winuser::PM_REMOVE
+            } else {
+                winuser::PM_NOREMOVE
+            },
+        )
+    } == 0
+    {
+        Ok(None)
+    } else {
+        Ok(Some(unsafe { m.assume_init() }))
+    }
+}
+
 /// Translate the message from the Win32 event loop.
 #[inline]
 pub fn translate_message(m: &MSG) {
@@ -78,8 +113,323 @@ pub fn translate_message(m: &MSG) {
 }
 
 /// Dispatch the message from the Win32 event loop.
+///
+/// If a `WM_PAINT` handler returns without validating its window (usually by calling
+/// `begin_paint` and dropping the resulting `DeviceContext`), Windows will keep sending
+/// `WM_PAINT` forever. To avoid this, any update region left over after a `WM_PAINT` is
+/// dispatched is validated. Note that this means a window cannot invalidate itself
+/// from inside of its `WM_PAINT` handler after its painting has finished.
 #[inline]
 pub fn dispatch_message(m: &MSG) {
     // note: the function returns the return value of the WndProc. This should be ignored.
     unsafe { winuser::DispatchMessageA(m) };
+
+    if m.message == winuser::WM_PAINT
+        && !m.hwnd.is_null()
+        && unsafe { winuser::GetUpdateRect(m.hwnd, ptr::null_mut(), FALSE) } != 0
+    {
+        unsafe { winuser::ValidateRect(m.hwnd, ptr::null()) };
+    }
+}
+
+/// Run the Win32 event loop until `WM_QUIT` is received, and return the exit code that
+/// was passed to `PostQuitMessage`.
+///
+/// `pre_dispatch` is called on every message before it is translated and dispatched.
+/// This can be used for things like accelerators; however, if a message must not be
+/// translated and dispatched after it's been handled (as is the case with
+/// `translate_accelerator` and `is_dialog_message`), a loop should be written by hand.
+pub fn run_event_loop<F: FnMut(&MSG)>(mut pre_dispatch: F) -> crate::Result<i32> {
+    loop {
+        let mut m: MaybeUninit<MSG> = MaybeUninit::uninit();
+
+        match unsafe { winuser::GetMessageA(m.as_mut_ptr(), ptr::null_mut(), 0, 0) }.cmp(&0) {
+            Ordering::Greater => {
+                let m = unsafe { m.assume_init() };
+                pre_dispatch(&m);
+                translate_message(&m);
+                dispatch_message(&m);
+            }
+            Ordering::Equal => {
+                // the wParam of WM_QUIT is the exit code
+                return Ok(unsafe { m.assume_init() }.wParam as i32);
+            }
+            Ordering::Less => {
+                return Err(crate::win32_error(crate::Win32Function::GetMessageA));
+            }
+        }
+    }
+}
+
+/// Process every message that is currently in the queue, without waiting for new ones.
+///
+/// This stops early if a `WM_QUIT` message is found. That message is posted again, so
+/// that the next call to `get_message` or `run_event_loop` still sees it.
+pub fn pump_pending() -> crate::Result<()> {
+    while let Some(m) = peek_message(true)? {
+        if m.message == winuser::WM_QUIT {
+            unsafe { winuser::PostQuitMessage(m.wParam as c_int) };
+            break;
+        }
+
+        translate_message(&m);
+        dispatch_message(&m);
+    }
+
+    Ok(())
+}
+
+/// Place a message in the queue of the thread that created the window, and return
+/// without waiting for it to be processed.
+#[inline]
+pub fn post_message(
+    window: &dyn GenericWindow,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> crate::Result<()> {
+    if unsafe { winuser::PostMessageA(window.try_hwnd()?.as_mut(), msg, wparam, lparam) } == 0 {
+        Err(crate::win32_error(crate::Win32Function::PostMessageA))
+    } else {
+        Ok(())
+    }
+}
+
+/// Send a message to a window, and wait for its window procedure to process it. Returns
+/// the value returned by the window procedure.
+#[inline]
+pub fn send_message(
+    window: &dyn GenericWindow,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe { winuser::SendMessageA(window.hwnd().as_mut(), msg, wparam, lparam) }
+}
+
+/// Process a message meant for a dialog-like window, which gives it keyboard navigation
+/// between its child controls (tab order, arrow keys and default buttons). This works
+/// best when the window has the `CONTROL_PARENT` extended style.
+///
+/// If this function returns true, the message has already been processed and should not
+/// be passed to `translate_message` or `dispatch_message`.
+#[inline]
+pub fn is_dialog_message(window: &dyn GenericWindow, msg: &mut MSG) -> bool {
+    unsafe { winuser::IsDialogMessageW(window.hwnd().as_mut(), msg) != 0 }
+}
+
+/// Translate a keystroke matching an entry in the accelerator table into a `WM_COMMAND`
+/// message for the given window.
+///
+/// If this function returns true, the message has already been processed and should not
+/// be passed to `translate_message` or `dispatch_message`.
+#[inline]
+pub fn translate_accelerator(
+    window: &dyn GenericWindow,
+    table: &AcceleratorTable,
+    msg: &mut MSG,
+) -> bool {
+    unsafe {
+        winuser::TranslateAcceleratorW(window.hwnd().as_mut(), table.haccel().as_mut(), msg) != 0
+    }
+}
+
+/// Helpers for reading the words packed into message parameters.
+pub mod params {
+    use super::LParamPoint;
+    use cty::c_int;
+    use euclid::default::Point2D;
+    use winapi::shared::minwindef::{HIWORD, LOWORD, LPARAM, WPARAM};
+
+    /// Get the low word of a `WPARAM`.
+    #[inline]
+    pub fn low_word(w: WPARAM) -> u16 {
+        LOWORD(w as u32)
+    }
+
+    /// Get the high word of a `WPARAM`.
+    #[inline]
+    pub fn high_word(w: WPARAM) -> u16 {
+        HIWORD(w as u32)
+    }
+
+    /// Get the low word of an `LPARAM` as a signed value, like `GET_X_LPARAM`.
+    #[inline]
+    pub fn signed_low_word(l: LPARAM) -> i16 {
+        LOWORD(l as u32) as i16
+    }
+
+    /// Get the high word of an `LPARAM` as a signed value, like `GET_Y_LPARAM`.
+    #[inline]
+    pub fn signed_high_word(l: LPARAM) -> i16 {
+        HIWORD(l as u32) as i16
+    }
+
+    /// Get the point from the `lParam` of a mouse message. The coordinates are
+    /// sign-extended, since they can be negative on multi-monitor setups.
+    #[inline]
+    pub fn mouse_point(lparam: LPARAM) -> Point2D<c_int> {
+        LParamPoint(lparam).point()
+    }
+
+    /// Get the ID of the menu item, accelerator or control from the `wParam` of a
+    /// `WM_COMMAND` message.
+    #[inline]
+    pub fn command_id(wparam: WPARAM) -> u16 {
+        low_word(wparam)
+    }
+
+    /// Get the notification code from the `wParam` of a `WM_COMMAND` message. This is 0
+    /// for menu items and 1 for accelerators.
+    #[inline]
+    pub fn command_code(wparam: WPARAM) -> u16 {
+        high_word(wparam)
+    }
+}
+
+/// The `lParam` of a message that packs a point or a size into its low and high words,
+/// such as `WM_MOUSEMOVE` or `WM_SIZE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LParamPoint(pub LPARAM);
+
+impl LParamPoint {
+    /// Read the `lParam` as a point. Both coordinates are sign-extended, since points to the
+    /// left of or above the primary monitor have negative coordinates.
+    #[inline]
+    pub fn point(self) -> Point2D<c_int> {
+        Point2D::new(
+            params::signed_low_word(self.0).into(),
+            params::signed_high_word(self.0).into(),
+        )
+    }
+
+    /// Read the `lParam` as a size. Sizes are never negative, so the words are not
+    /// sign-extended.
+    #[inline]
+    pub fn size(self) -> Size2D<c_int> {
+        let lparam = self.0 as u32;
+        Size2D::new(LOWORD(lparam) as c_int, HIWORD(lparam) as c_int)
+    }
+}
+
+impl From<LParamPoint> for Point2D<c_int> {
+    #[inline]
+    fn from(lparam: LParamPoint) -> Self {
+        lparam.point()
+    }
+}
+
+impl From<LParamPoint> for Size2D<c_int> {
+    #[inline]
+    fn from(lparam: LParamPoint) -> Self {
+        lparam.size()
+    }
+}
+
+bitflags::bitflags! {
+    #[doc = "The mouse buttons and modifier keys held down during a mouse message"]
+    pub struct MouseButtons : WPARAM {
+        const NONE = 0;
+        const LEFT = winuser::MK_LBUTTON;
+        const RIGHT = winuser::MK_RBUTTON;
+        const MIDDLE = winuser::MK_MBUTTON;
+        const X1 = winuser::MK_XBUTTON1;
+        const X2 = winuser::MK_XBUTTON2;
+        const SHIFT = winuser::MK_SHIFT;
+        const CONTROL = winuser::MK_CONTROL;
+    }
+}
+
+/// A window message, decoded from its raw message code and parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowMessage {
+    /// `WM_PAINT`
+    Paint,
+    /// `WM_CLOSE`
+    Close,
+    /// `WM_DESTROY`
+    Destroy,
+    /// `WM_SIZE`, with the new size of the client area.
+    Size { width: u16, height: u16 },
+    /// `WM_MOUSEMOVE`, with the cursor position in client coordinates.
+    MouseMove {
+        pt: Point2D<c_int>,
+        buttons: MouseButtons,
+    },
+    /// `WM_COMMAND`, with the ID of the menu item, accelerator or control, and the
+    /// notification code.
+    Command { id: u16, code: u16 },
+    /// `WM_KEYDOWN`, with the virtual key code.
+    KeyDown { vk: u32 },
+    /// Any other message.
+    Other {
+        msg: UINT,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    },
+}
+
+impl WindowMessage {
+    /// Decode a message from the arguments of a window procedure.
+    pub fn decode(msg: UINT, wparam: WPARAM, lparam: LPARAM) -> Self {
+        match msg {
+            winuser::WM_PAINT => Self::Paint,
+            winuser::WM_CLOSE => Self::Close,
+            winuser::WM_DESTROY => Self::Destroy,
+            winuser::WM_SIZE => {
+                let lparam = lparam as u32;
+                Self::Size {
+                    width: LOWORD(lparam),
+                    height: HIWORD(lparam),
+                }
+            }
+            winuser::WM_MOUSEMOVE => Self::MouseMove {
+                pt: params::mouse_point(lparam),
+                buttons: MouseButtons::from_bits_truncate(wparam),
+            },
+            winuser::WM_COMMAND => Self::Command {
+                id: params::command_id(wparam),
+                code: params::command_code(wparam),
+            },
+            winuser::WM_KEYDOWN => Self::KeyDown { vk: wparam as u32 },
+            msg => Self::Other {
+                msg,
+                wparam,
+                lparam,
+            },
+        }
+    }
+}
+
+/// Decode the parameters of a `WM_SETCURSOR` message into the window that contains the
+/// cursor, the hit-test code (such as `HTCLIENT` or `HTLEFT`) and the mouse message that
+/// triggered it.
+///
+/// A handler that changes the cursor with `set_cursor` should return `TRUE` to stop
+/// further processing; otherwise, it should pass the message on to `DefWindowProc`.
+#[inline]
+pub fn decode_set_cursor(wparam: WPARAM, lparam: LPARAM) -> (DroplessWindow, u16, u16) {
+    let window = unsafe { DroplessWindow::new(wparam as _) };
+    let lparam = lparam as u32;
+    (window, LOWORD(lparam), HIWORD(lparam))
+}
+
+/// Decode the `lParam` of a `WM_COPYDATA` message into the ID and the data that were
+/// sent. The data is copied out, since the buffer is only valid while the message is
+/// being processed.
+///
+/// # Safety
+///
+/// `lparam` must be the `lParam` of a `WM_COPYDATA` message that is currently being
+/// processed.
+#[inline]
+pub unsafe fn decode_copy_data(lparam: LPARAM) -> (usize, Vec<u8>) {
+    let copy_data = &*(lparam as *const COPYDATASTRUCT);
+    let data = if copy_data.cbData == 0 || copy_data.lpData.is_null() {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(copy_data.lpData as *const u8, copy_data.cbData as usize).to_vec()
+    };
+
+    (copy_data.dwData, data)
 }
@@ -0,0 +1,105 @@
+/* -----------------------------------------------------------------------------------
+ * src/raw_handle.rs - raw-window-handle integration.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::{DroplessWindow, GenericWindow, WeakWindow, Window};
+use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle, Win32WindowHandle,
+    WindowsDisplayHandle,
+};
+use winapi::ctypes::c_void;
+
+// fill a raw window handle with a window and the current module
+fn win32_handle<T: GenericWindow + ?Sized>(window: &T) -> RawWindowHandle {
+    let mut handle = Win32WindowHandle::empty();
+    handle.hwnd = window.hwnd().as_ptr() as *mut c_void;
+    handle.hinstance = crate::module_instance().as_ptr() as *mut c_void;
+    RawWindowHandle::Win32(handle)
+}
+
+unsafe impl HasRawWindowHandle for Window {
+    #[inline]
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        win32_handle(self)
+    }
+}
+
+unsafe impl HasRawDisplayHandle for Window {
+    #[inline]
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Windows(WindowsDisplayHandle::empty())
+    }
+}
+
+unsafe impl HasRawWindowHandle for DroplessWindow {
+    #[inline]
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        win32_handle(self)
+    }
+}
+
+unsafe impl HasRawDisplayHandle for DroplessWindow {
+    #[inline]
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Windows(WindowsDisplayHandle::empty())
+    }
+}
+
+/// # Panics
+///
+/// Getting the window handle panics if the window has already been dropped, just like
+/// `GenericWindow::hwnd`.
+unsafe impl HasRawWindowHandle for WeakWindow {
+    #[inline]
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        win32_handle(self)
+    }
+}
+
+unsafe impl HasRawDisplayHandle for WeakWindow {
+    #[inline]
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Windows(WindowsDisplayHandle::empty())
+    }
+}
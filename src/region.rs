@@ -0,0 +1,133 @@
+/* -----------------------------------------------------------------------------------
+ * src/region.rs - A wrapper around GDI regions.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+use crate::mutexes::Mutex;
+use core::{
+    mem,
+    ptr::{self, NonNull},
+    sync::atomic::AtomicPtr,
+};
+use cty::c_int;
+use euclid::default::Rect;
+use winapi::{
+    ctypes::c_void,
+    shared::minwindef::{HRGN, HRGN__},
+    um::wingdi,
+};
+
+/// A region, made up of rectangles, ellipses and polygons.
+#[repr(transparent)]
+pub struct Region {
+    hrgn: Mutex<AtomicPtr<HRGN__>>,
+}
+
+impl Region {
+    /// Create a new rectangular region.
+    #[inline]
+    pub fn rect(rect: Rect<c_int>) -> crate::Result<Self> {
+        let hrgn = unsafe {
+            wingdi::CreateRectRgn(
+                rect.origin.x,
+                rect.origin.y,
+                rect.origin.x + rect.size.width,
+                rect.origin.y + rect.size.height,
+            )
+        };
+
+        if hrgn.is_null() {
+            Err(crate::win32_error(crate::Win32Function::CreateRectRgn))
+        } else {
+            Ok(Self {
+                hrgn: Mutex::new(AtomicPtr::new(hrgn)),
+            })
+        }
+    }
+
+    /// Create a copy of this region.
+    pub fn try_clone(&self) -> crate::Result<Self> {
+        let copy = Self::rect(Rect::zero())?;
+        if unsafe {
+            wingdi::CombineRgn(
+                copy.hrgn().as_mut(),
+                self.hrgn().as_mut(),
+                ptr::null_mut(),
+                wingdi::RGN_COPY,
+            )
+        } == wingdi::ERROR
+        {
+            Err(crate::win32_error(crate::Win32Function::CombineRgn))
+        } else {
+            Ok(copy)
+        }
+    }
+
+    // give up ownership of the region handle, for functions that take ownership of it
+    #[inline]
+    pub(crate) fn into_raw(self) -> HRGN {
+        let hrgn = unsafe { self.hrgn() }.as_ptr();
+        mem::forget(self);
+        hrgn
+    }
+
+    /// Get the handle to this region.
+    ///
+    /// # Safety
+    ///
+    /// This function copies the pointer out of an AtomicPtr and is thus unsound.
+    #[inline]
+    pub unsafe fn hrgn(&self) -> NonNull<HRGN__> {
+        let mut p = self.hrgn.lock();
+        let ptr = p.get_mut();
+        debug_assert!(!ptr.is_null());
+        NonNull::new_unchecked(*ptr)
+    }
+}
+
+impl Drop for Region {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { crate::delete_gdi_object(*self.hrgn.lock().get_mut() as *mut c_void, "region") };
+    }
+}
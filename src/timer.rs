@@ -0,0 +1,138 @@
+/* -----------------------------------------------------------------------------------
+ * src/timer.rs - Timers that invoke Rust closures.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+//! Timers that are not tied to a window.
+
+use crate::mutexes::Mutex;
+use alloc::{boxed::Box, collections::BTreeMap};
+use core::{marker::PhantomData, ptr};
+use winapi::{
+    shared::{
+        basetsd::UINT_PTR,
+        minwindef::{DWORD, UINT},
+        windef::HWND,
+    },
+    um::winuser,
+};
+
+// a timer callback, stored while the timer is alive
+struct TimerCallback(Option<Box<dyn FnMut()>>);
+
+// timer callbacks are only ever called from the thread that created the timer
+unsafe impl Send for TimerCallback {}
+
+lazy_static::lazy_static! {
+    static ref TIMER_CALLBACKS: Mutex<BTreeMap<UINT_PTR, TimerCallback>> =
+        Mutex::new(BTreeMap::new());
+}
+
+unsafe extern "system" fn timer_trampoline(_hwnd: HWND, _msg: UINT, id: UINT_PTR, _time: DWORD) {
+    // take the callback out of the table so that it can set or kill timers itself
+    let callback = match TIMER_CALLBACKS.lock().get_mut(&id) {
+        Some(TimerCallback(c)) => c.take(),
+        None => None,
+    };
+
+    if let Some(mut callback) = callback {
+        callback();
+
+        // put it back, unless the timer was killed during the callback
+        if let Some(TimerCallback(c)) = TIMER_CALLBACKS.lock().get_mut(&id) {
+            *c = Some(callback);
+        }
+    }
+}
+
+/// A guard for a running timer. The timer is killed and its callback dropped when this
+/// guard is dropped.
+#[derive(Debug)]
+pub struct TimerGuard {
+    id: UINT_PTR,
+    // the callback may not be Send, and the timer can only be killed from its own thread
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl TimerGuard {
+    /// Get the ID of the timer.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Drop for TimerGuard {
+    fn drop(&mut self) {
+        unsafe { winuser::KillTimer(ptr::null_mut(), self.id) };
+        TIMER_CALLBACKS.lock().remove(&self.id);
+    }
+}
+
+/// Set a timer that calls the given closure every `interval_ms` milliseconds. The
+/// closure is run from the message loop of the current thread.
+pub fn set_timer_callback<F: FnMut() + 'static>(
+    interval_ms: u32,
+    callback: F,
+) -> crate::Result<TimerGuard> {
+    let id = unsafe {
+        winuser::SetTimer(
+            ptr::null_mut(),
+            0,
+            interval_ms as UINT,
+            Some(timer_trampoline),
+        )
+    };
+
+    if id == 0 {
+        Err(crate::win32_error(crate::Win32Function::SetTimer))
+    } else {
+        TIMER_CALLBACKS
+            .lock()
+            .insert(id, TimerCallback(Some(Box::new(callback))));
+        Ok(TimerGuard {
+            id,
+            _thread_bound: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,205 @@
+/* -----------------------------------------------------------------------------------
+ * src/uxtheme.rs - Wrappers around the visual styles API.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+//! Theme-aware drawing, using the visual styles API. This requires the `uxtheme` feature.
+
+use crate::{DeviceContext, GenericWindow, WString};
+use core::ptr::{self, NonNull};
+use cty::c_int;
+use euclid::default::Rect;
+use winapi::{
+    ctypes::c_void,
+    shared::{
+        minwindef::{FALSE, TRUE},
+        winerror::FAILED,
+    },
+    um::uxtheme,
+};
+
+/// A buffered device context, created by `DeviceContext::begin_buffered_paint`.
+/// Drawing done on this DC is copied to the window when it is dropped.
+pub struct BufferedDc {
+    buffer: NonNull<c_void>,
+    dc: DeviceContext,
+    // keep the painting DC alive until the buffer is flushed
+    _target: DeviceContext,
+}
+
+impl BufferedDc {
+    /// Get the device context to draw onto.
+    #[inline]
+    pub fn dc(&self) -> &DeviceContext {
+        &self.dc
+    }
+}
+
+impl Drop for BufferedDc {
+    fn drop(&mut self) {
+        unsafe {
+            uxtheme::EndBufferedPaint(self.buffer.as_ptr(), TRUE);
+            uxtheme::BufferedPaintUnInit();
+        }
+    }
+}
+
+impl DeviceContext {
+    /// Begin painting on a window through an off-screen buffer, for flicker-free
+    /// drawing. This should be called in response to `WM_PAINT`, in place of
+    /// `begin_paint`.
+    pub fn begin_buffered_paint<T: GenericWindow + ?Sized>(
+        window: &T,
+        rect: Rect<c_int>,
+    ) -> crate::Result<BufferedDc> {
+        let hr = unsafe { uxtheme::BufferedPaintInit() };
+        if FAILED(hr) {
+            return Err(crate::hresult_error(
+                hr,
+                crate::Win32Function::BufferedPaintInit,
+            ));
+        }
+
+        let target = match DeviceContext::begin_paint(window) {
+            Ok(target) => target,
+            Err(e) => {
+                unsafe { uxtheme::BufferedPaintUnInit() };
+                return Err(e);
+            }
+        };
+
+        let rect = crate::eurect_to_winrect(rect);
+        let mut hdc = ptr::null_mut();
+        let buffer = unsafe {
+            uxtheme::BeginBufferedPaint(
+                target.hdc().as_mut(),
+                &rect,
+                uxtheme::BPBF_COMPATIBLEBITMAP,
+                ptr::null_mut(),
+                &mut hdc,
+            )
+        };
+
+        match NonNull::new(buffer) {
+            Some(buffer) if !hdc.is_null() => Ok(BufferedDc {
+                buffer,
+                dc: DeviceContext::borrowed(hdc),
+                _target: target,
+            }),
+            buffer => {
+                let err = crate::win32_error(crate::Win32Function::BeginBufferedPaint);
+                unsafe {
+                    // a buffer without a DC still has to be ended, or it leaks
+                    if let Some(buffer) = buffer {
+                        uxtheme::EndBufferedPaint(buffer.as_ptr(), FALSE);
+                    }
+                    uxtheme::BufferedPaintUnInit();
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Theme data for a window class, used to draw parts of native controls.
+pub struct Theme {
+    htheme: NonNull<c_void>,
+}
+
+impl Theme {
+    /// Open the theme data for a window and a list of class names separated by
+    /// semicolons, such as `"BUTTON"`.
+    pub fn open<T: GenericWindow + ?Sized>(window: &T, class: &str) -> crate::Result<Self> {
+        let class = WString::from(class);
+        let htheme = unsafe { uxtheme::OpenThemeData(window.try_hwnd()?.as_mut(), class.as_ptr()) };
+
+        match NonNull::new(htheme) {
+            Some(htheme) => Ok(Self { htheme }),
+            None => Err(crate::win32_error(crate::Win32Function::OpenThemeData)),
+        }
+    }
+
+    /// Draw the background of a part of a control. The part and state IDs are defined
+    /// in `vsstyle.h`; for instance, a pushbutton is part `1` (`BP_PUSHBUTTON`) and its
+    /// normal state is `1` (`PBS_NORMAL`).
+    pub fn draw_background(
+        &self,
+        dc: &DeviceContext,
+        part: i32,
+        state: i32,
+        rect: Rect<c_int>,
+    ) -> crate::Result<()> {
+        let rect = crate::eurect_to_winrect(rect);
+        let hr = unsafe {
+            uxtheme::DrawThemeBackground(
+                self.htheme.as_ptr(),
+                dc.hdc().as_mut(),
+                part,
+                state,
+                &rect,
+                ptr::null(),
+            )
+        };
+
+        if FAILED(hr) {
+            Err(crate::hresult_error(
+                hr,
+                crate::Win32Function::DrawThemeBackground,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the handle to this theme.
+    #[inline]
+    pub fn htheme(&self) -> NonNull<c_void> {
+        self.htheme
+    }
+}
+
+impl Drop for Theme {
+    fn drop(&mut self) {
+        unsafe { uxtheme::CloseThemeData(self.htheme.as_ptr()) };
+    }
+}
@@ -43,35 +43,40 @@
  * ----------------------------------------------------------------------------------
  */
 
-use crate::{mutexes::Mutex, DeviceContext};
+use crate::{
+    mutexes::Mutex, Bitmap, Brush, DeviceContext, Menu, MenuRef, Region, SubclassGuard, SysColor,
+    SystemCursor, WStr, WString,
+};
 use alloc::{
     boxed::Box,
+    collections::BTreeMap,
     format,
     string::String,
     sync::{Arc, Weak},
+    vec,
+    vec::Vec,
 };
 use core::{
-    any::Any,
-    convert::TryInto,
+    any::{Any, TypeId},
     fmt, mem,
     ptr::{self, NonNull},
     sync::atomic::AtomicPtr,
 };
 use cty::c_int;
-use euclid::default::{Point2D, Rect};
+use euclid::default::{Point2D, Rect, Size2D};
 use maybe_uninit::MaybeUninit;
 use winapi::{
     ctypes::c_void,
     shared::{
         basetsd::LONG_PTR,
-        minwindef::{DWORD, FALSE, TRUE, UINT},
-        ntdef::LPCSTR,
-        windef::{HBRUSH, HWND, HWND__, POINT},
+        minwindef::{BOOL, DWORD, FALSE, FARPROC, LPARAM, LRESULT, MAX_PATH, TRUE, UINT, WPARAM},
+        ntdef::{LPCSTR, WCHAR},
+        windef::{COLORREF, HBRUSH, HICON, HMENU, HWND, HWND__, POINT, RECT, SIZE},
     },
     um::{
-        errhandlingapi,
+        errhandlingapi, libloaderapi, processthreadsapi, wingdi,
         winuser::{
-            self, COLOR_WINDOW, IDC_ARROW, IDI_APPLICATION, WINDOWPLACEMENT, WNDCLASSEXA, WNDPROC,
+            self, COPYDATASTRUCT, IDC_ARROW, IDI_APPLICATION, WINDOWPLACEMENT, WNDCLASSEXA, WNDPROC,
         },
     },
 };
@@ -82,6 +87,24 @@ pub struct OwnedWindowClass {
     inner: WNDCLASSEXA,
     is_registered: bool,
     class_name: String,
+    background: Option<Arc<Brush>>, // keeps an owned background brush alive
+    // the brush the class was registered with, if it was replaced since then
+    registered_background: Option<Arc<Brush>>,
+    menu_name: Option<Arc<String>>, // shared, so clones keep pointing at live memory
+}
+
+/// The brush used to paint the background of a window class.
+pub enum BackgroundBrush {
+    /// One of the system colors. This follows the system theme.
+    System(SysColor),
+    /// A brush owned by the window class.
+    Owned(Brush),
+}
+
+// the value of hbrBackground that tells Windows to use a system color
+#[inline]
+fn sys_color_background(color: SysColor) -> HBRUSH {
+    (color as usize + 1) as HBRUSH
 }
 
 unsafe impl Send for OwnedWindowClass {}
@@ -108,7 +131,7 @@ impl OwnedWindowClass {
         let inner = WNDCLASSEXA {
             cbSize: mem::size_of::<WNDCLASSEXA>() as UINT,
             lpfnWndProc: Some(winuser::DefWindowProcW),
-            hInstance: unsafe { crate::MODULE_INFO.lock().handle().as_mut() },
+            hInstance: crate::module_instance().as_ptr(),
             lpszClassName: name.as_ptr() as LPCSTR,
             hIcon: icon,
             hIconSm: icon,
@@ -116,9 +139,7 @@ impl OwnedWindowClass {
             cbClsExtra: 0,
             cbWndExtra: 0,
             hCursor: unsafe { winuser::LoadCursorW(ptr::null_mut(), IDC_ARROW) },
-            hbrBackground: unsafe {
-                mem::transmute::<usize, HBRUSH>((COLOR_WINDOW + 1).try_into().unwrap())
-            },
+            hbrBackground: sys_color_background(SysColor::Window),
             lpszMenuName: ptr::null(),
         };
 
@@ -126,6 +147,9 @@ impl OwnedWindowClass {
             inner,
             is_registered: false,
             class_name: name,
+            background: None,
+            registered_background: None,
+            menu_name: None,
         }
     }
 
@@ -157,6 +181,50 @@ impl OwnedWindowClass {
         self.inner.style = style;
     }
 
+    /// Set the brush used to paint the background of windows of this class. The default is
+    /// the system window color. An owned brush is kept alive by this class, so the class
+    /// should outlive any windows created from it.
+    ///
+    /// This only takes effect when the class is registered. If the class is already
+    /// registered, the brush it was registered with is kept alive until `register` is
+    /// called again, since Windows still uses it.
+    pub fn set_background_brush(&mut self, brush: BackgroundBrush) {
+        if self.is_registered && self.registered_background.is_none() {
+            self.registered_background = self.background.take();
+        }
+
+        match brush {
+            BackgroundBrush::System(color) => {
+                self.inner.hbrBackground = sys_color_background(color);
+                self.background = None;
+            }
+            BackgroundBrush::Owned(brush) => {
+                self.inner.hbrBackground = unsafe { brush.hbrush().as_ptr() };
+                self.background = Some(Arc::new(brush));
+            }
+        }
+    }
+
+    /// Set the cursor shown over windows of this class. The default is the arrow.
+    pub fn set_cursor(&mut self, cursor: SystemCursor) {
+        self.inner.hCursor = unsafe { winuser::LoadCursorW(ptr::null_mut(), cursor.resource()) };
+    }
+
+    /// Set the icon of windows of this class. The default is the application icon.
+    pub fn set_icon(&mut self, icon: HICON) {
+        self.inner.hIcon = icon;
+        self.inner.hIconSm = icon;
+    }
+
+    /// Set the name of the menu resource that windows of this class use by default.
+    pub fn set_menu_name(&mut self, name: String) {
+        let mut name = name;
+        name.push('\0');
+        let name = Arc::new(name);
+        self.inner.lpszMenuName = name.as_ptr() as LPCSTR;
+        self.menu_name = Some(name);
+    }
+
     /// Register this class. This function will unregister, then re-register the class
     /// if it is already registered.
     pub fn register(&mut self) -> crate::Result<()> {
@@ -165,7 +233,7 @@ impl OwnedWindowClass {
             if unsafe {
                 winuser::UnregisterClassA(
                     self.class_name.as_ptr() as LPCSTR,
-                    crate::MODULE_INFO.lock().handle().as_mut(),
+                    crate::module_instance().as_ptr(),
                 )
             } == 0
             {
@@ -180,11 +248,111 @@ impl OwnedWindowClass {
             Err(crate::win32_error(crate::Win32Function::RegisterClassExA))
         } else {
             self.is_registered = true;
+            // the old class is gone, so its background brush is no longer in use
+            self.registered_background = None;
             Ok(())
         }
     }
 }
 
+/// A builder for an `OwnedWindowClass`. Fields that are not set keep the defaults used by
+/// `OwnedWindowClass::new`, except for the window procedure, which must be set.
+pub struct WindowClassBuilder {
+    name: String,
+    window_proc: WNDPROC,
+    style: UINT,
+    background: Option<BackgroundBrush>,
+    cursor: Option<SystemCursor>,
+    icon: Option<HICON>,
+    menu_name: Option<String>,
+}
+
+impl WindowClassBuilder {
+    /// Start building a window class with the given name.
+    #[inline]
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            window_proc: None,
+            style: 0,
+            background: None,
+            cursor: None,
+            icon: None,
+            menu_name: None,
+        }
+    }
+
+    /// Set the window procedure for the class.
+    #[inline]
+    pub fn window_proc(mut self, wndproc: WNDPROC) -> Self {
+        self.window_proc = wndproc;
+        self
+    }
+
+    /// Set the style for the class.
+    #[inline]
+    pub fn style(mut self, style: UINT) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set the background brush for the class.
+    #[inline]
+    pub fn background(mut self, brush: BackgroundBrush) -> Self {
+        self.background = Some(brush);
+        self
+    }
+
+    /// Set the cursor for the class.
+    #[inline]
+    pub fn cursor(mut self, cursor: SystemCursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Set the icon for the class.
+    #[inline]
+    pub fn icon(mut self, icon: HICON) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Set the name of the class's default menu resource.
+    #[inline]
+    pub fn menu_name(mut self, name: String) -> Self {
+        self.menu_name = Some(name);
+        self
+    }
+
+    /// Build the window class. This fails if no window procedure was set. The class still
+    /// has to be registered with `OwnedWindowClass::register`.
+    pub fn build(self) -> crate::Result<OwnedWindowClass> {
+        if self.window_proc.is_none() {
+            return Err(crate::Error::StaticMsg(
+                "A window class needs a window procedure",
+            ));
+        }
+
+        let mut class = OwnedWindowClass::new(self.name);
+        class.set_window_proc(self.window_proc);
+        class.set_style(self.style);
+        if let Some(background) = self.background {
+            class.set_background_brush(background);
+        }
+        if let Some(cursor) = self.cursor {
+            class.set_cursor(cursor);
+        }
+        if let Some(icon) = self.icon {
+            class.set_icon(icon);
+        }
+        if let Some(menu_name) = self.menu_name {
+            class.set_menu_name(menu_name);
+        }
+
+        Ok(class)
+    }
+}
+
 /// A window class; either a reference to a window class or a full, owned window class.
 pub trait WindowClass {
     /// Convert this item into the name of the class.
@@ -243,6 +411,47 @@ bitflags::bitflags! {
     }
 }
 
+impl WindowStyle {
+    /// The style of a typical main application window: resizable, with a title bar and
+    /// window menu, and visible as soon as it is created.
+    #[inline]
+    pub fn main_window() -> Self {
+        Self::OVERLAPPED_WINDOW | Self::VISIBLE
+    }
+
+    /// The style of a dialog box: a popup window with a title bar and window menu.
+    #[inline]
+    pub fn dialog() -> Self {
+        Self::POPUP | Self::CAPTION | Self::SYSMENU
+    }
+
+    /// The style of a control inside of another window.
+    #[inline]
+    pub fn child_control() -> Self {
+        Self::CHILD | Self::VISIBLE
+    }
+
+    /// The style of a splash screen: a popup window with no border or title bar.
+    #[inline]
+    pub fn splash() -> Self {
+        Self::POPUP
+    }
+
+    /// Check for style combinations that would make window creation fail, or that
+    /// contradict each other. Returns a description of the first problem found.
+    pub fn validate(&self, has_parent: bool) -> Result<(), &'static str> {
+        if self.contains(Self::CHILD) && self.contains(Self::POPUP) {
+            Err("The CHILD and POPUP window styles cannot be combined")
+        } else if self.contains(Self::CHILD) && !has_parent {
+            Err("A window with the CHILD style must have a parent window")
+        } else if self.contains(Self::MAXIMIZE) && self.contains(Self::MINIMIZE) {
+            Err("A window cannot be created both maximized and minimized")
+        } else {
+            Ok(())
+        }
+    }
+}
+
 bitflags::bitflags! {
     pub struct ExtendedWindowStyle : DWORD {
         const NONE = 0;
@@ -276,6 +485,47 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    #[doc = "Flags for changing the size and position of a window"]
+    pub struct SetWindowPosFlags : UINT {
+        const NONE = 0;
+        const ASYNC_WINDOW_POS = winuser::SWP_ASYNCWINDOWPOS;
+        const DEFER_ERASE = winuser::SWP_DEFERERASE;
+        const DRAW_FRAME = winuser::SWP_DRAWFRAME;
+        const FRAME_CHANGED = winuser::SWP_FRAMECHANGED;
+        const HIDE_WINDOW = winuser::SWP_HIDEWINDOW;
+        const NO_ACTIVATE = winuser::SWP_NOACTIVATE;
+        const NO_COPY_BITS = winuser::SWP_NOCOPYBITS;
+        const NO_MOVE = winuser::SWP_NOMOVE;
+        const NO_OWNER_Z_ORDER = winuser::SWP_NOOWNERZORDER;
+        const NO_REDRAW = winuser::SWP_NOREDRAW;
+        const NO_REPOSITION = winuser::SWP_NOREPOSITION;
+        const NO_SEND_CHANGING = winuser::SWP_NOSENDCHANGING;
+        const NO_SIZE = winuser::SWP_NOSIZE;
+        const NO_Z_ORDER = winuser::SWP_NOZORDER;
+        const SHOW_WINDOW = winuser::SWP_SHOWWINDOW;
+    }
+}
+
+bitflags::bitflags! {
+    #[doc = "Flags for redrawing a window"]
+    pub struct RedrawFlags : UINT {
+        const NONE = 0;
+        const INVALIDATE = winuser::RDW_INVALIDATE;
+        const INTERNAL_PAINT = winuser::RDW_INTERNALPAINT;
+        const ERASE = winuser::RDW_ERASE;
+        const VALIDATE = winuser::RDW_VALIDATE;
+        const NO_INTERNAL_PAINT = winuser::RDW_NOINTERNALPAINT;
+        const NO_ERASE = winuser::RDW_NOERASE;
+        const NO_CHILDREN = winuser::RDW_NOCHILDREN;
+        const ALL_CHILDREN = winuser::RDW_ALLCHILDREN;
+        const UPDATE_NOW = winuser::RDW_UPDATENOW;
+        const ERASE_NOW = winuser::RDW_ERASENOW;
+        const FRAME = winuser::RDW_FRAME;
+        const NO_FRAME = winuser::RDW_NOFRAME;
+    }
+}
+
 /// Ways to show a window.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(i32)]
@@ -290,6 +540,7 @@ pub enum CmdShow {
     ShowNA = winuser::SW_SHOWNA,
     ShowNoActivate = winuser::SW_SHOWNOACTIVATE,
     ShowNormal = winuser::SW_SHOWNORMAL,
+    Restore = winuser::SW_RESTORE,
 }
 
 impl CmdShow {
@@ -329,6 +580,13 @@ pub trait GenericWindow {
     /// the window has already been dropped.
     fn hwnd(&self) -> NonNull<HWND__>;
 
+    /// Get the raw handle to this window, or an error if the window is a weak reference
+    /// to a window that has already been dropped.
+    #[inline]
+    fn try_hwnd(&self) -> crate::Result<NonNull<HWND__>> {
+        Ok(self.hwnd())
+    }
+
     /// Create a weak reference to this window.
     fn weak_reference(&self) -> WeakWindow;
 
@@ -339,18 +597,57 @@ pub trait GenericWindow {
             x: pt.x.into(),
             y: pt.y.into(),
         };
-        if unsafe { winuser::ScreenToClient(self.hwnd().as_mut(), &mut lp) } == 0 {
+        if unsafe { winuser::ScreenToClient(self.try_hwnd()?.as_mut(), &mut lp) } == 0 {
             Err(crate::win32_error(crate::Win32Function::ScreenToClient))
         } else {
             Ok(Point2D::new(lp.x.into(), lp.y.into()))
         }
     }
 
+    /// Get the position of the mouse cursor relative to this window's client area.
+    #[inline]
+    fn cursor_pos_client(&self) -> crate::Result<Point2D<c_int>> {
+        self.screen_to_client(crate::cursor_pos()?)
+    }
+
+    /// Get the bounds of this window's client area. The origin is always `(0, 0)`.
+    #[inline]
+    fn client_rect(&self) -> crate::Result<Rect<c_int>> {
+        let mut rect: MaybeUninit<RECT> = MaybeUninit::zeroed();
+        if unsafe { winuser::GetClientRect(self.try_hwnd()?.as_mut(), rect.as_mut_ptr()) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::GetClientRect))
+        } else {
+            Ok(crate::winrect_to_eurect(unsafe { rect.assume_init() }))
+        }
+    }
+
+    /// Get the bounds of this window, in screen coordinates.
+    #[inline]
+    fn window_rect(&self) -> crate::Result<Rect<c_int>> {
+        let mut rect: MaybeUninit<RECT> = MaybeUninit::zeroed();
+        if unsafe { winuser::GetWindowRect(self.try_hwnd()?.as_mut(), rect.as_mut_ptr()) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::GetWindowRect))
+        } else {
+            Ok(crate::winrect_to_eurect(unsafe { rect.assume_init() }))
+        }
+    }
+
+    /// Convert a point relative to this window into screen coordinates.
+    #[inline]
+    fn client_to_screen(&self, pt: Point2D<c_int>) -> crate::Result<Point2D<c_int>> {
+        let mut lp = POINT { x: pt.x, y: pt.y };
+        if unsafe { winuser::ClientToScreen(self.try_hwnd()?.as_mut(), &mut lp) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::ClientToScreen))
+        } else {
+            Ok(Point2D::new(lp.x, lp.y))
+        }
+    }
+
     /// Change the bounds of this window.
     fn reshape(&self, rect: Rect<c_int>) -> crate::Result<()> {
         // create the window placement struct
         let mut wp: MaybeUninit<WINDOWPLACEMENT> = MaybeUninit::zeroed();
-        let mut hwnd = self.hwnd();
+        let mut hwnd = self.try_hwnd()?;
 
         if unsafe { winuser::GetWindowPlacement(hwnd.as_mut(), wp.as_mut_ptr()) } == 0 {
             return Err(crate::win32_error(crate::Win32Function::GetWindowPlacement));
@@ -369,118 +666,926 @@ pub trait GenericWindow {
         }
     }
 
-    /// Enable or unenable this window.
+    /// Move this window to a rectangle relative to its parent's client area. For
+    /// top-level windows, the rectangle is in screen coordinates.
     #[inline]
-    fn enable(&self, do_display: bool) {
-        unsafe { winuser::EnableWindow(self.hwnd().as_mut(), crate::wboolify(do_display)) };
+    fn move_to_parent_coords(&self, parent_relative: Rect<c_int>) -> crate::Result<()> {
+        if unsafe {
+            winuser::MoveWindow(
+                self.try_hwnd()?.as_mut(),
+                parent_relative.origin.x,
+                parent_relative.origin.y,
+                parent_relative.size.width,
+                parent_relative.size.height,
+                TRUE,
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::MoveWindow))
+        } else {
+            Ok(())
+        }
     }
 
-    /// Show the window.
+    /// Move this window to a rectangle in screen coordinates. If this is a child window,
+    /// the rectangle is converted into its parent's client coordinates first.
+    fn place_at_screen(&self, screen_rect: Rect<c_int>) -> crate::Result<()> {
+        let parent = unsafe { winuser::GetAncestor(self.try_hwnd()?.as_mut(), winuser::GA_PARENT) };
+
+        // top-level windows are parented to the desktop, which uses screen coordinates
+        let origin = if parent.is_null() || parent == unsafe { winuser::GetDesktopWindow() } {
+            screen_rect.origin
+        } else {
+            unsafe { DroplessWindow::new(parent) }.screen_to_client(screen_rect.origin)?
+        };
+
+        self.move_to_parent_coords(Rect::new(origin, screen_rect.size))
+    }
+
+    /// Change the size, position and Z-order of this window using `SetWindowPos`. Parts
+    /// of `bounds` are ignored depending on the flags passed in.
     #[inline]
-    fn show(&self, cmd_show: CmdShow) {
-        unsafe { winuser::ShowWindow(self.hwnd().as_mut(), cmd_show as c_int) };
+    fn set_window_pos(&self, bounds: Rect<c_int>, flags: SetWindowPosFlags) -> crate::Result<()> {
+        set_window_pos(self.try_hwnd()?, ptr::null_mut(), bounds, flags)
     }
 
-    /// Update the window.
+    /// Move this window without changing its size.
     #[inline]
-    fn update(&self) -> crate::Result<()> {
-        if unsafe { winuser::UpdateWindow(self.hwnd().as_mut()) } == 0 {
-            Err(crate::win32_error(crate::Win32Function::UpdateWindow))
+    fn set_position(&self, pt: Point2D<c_int>) -> crate::Result<()> {
+        self.set_window_pos(
+            Rect::new(pt, Size2D::zero()),
+            SetWindowPosFlags::NO_SIZE | SetWindowPosFlags::NO_Z_ORDER,
+        )
+    }
+
+    /// Change the size of this window without moving it.
+    #[inline]
+    fn set_size(&self, size: Size2D<c_int>) -> crate::Result<()> {
+        self.set_window_pos(
+            Rect::new(Point2D::zero(), size),
+            SetWindowPosFlags::NO_MOVE | SetWindowPosFlags::NO_Z_ORDER,
+        )
+    }
+
+    /// Change the position and size of this window. Unlike `reshape`, this does not
+    /// change the minimized or maximized state of the window.
+    #[inline]
+    fn set_bounds(&self, rect: Rect<c_int>) -> crate::Result<()> {
+        self.set_window_pos(rect, SetWindowPosFlags::NO_Z_ORDER)
+    }
+
+    /// Change the size of this window without moving it. This is the same as `set_size`.
+    #[inline]
+    fn resize(&self, size: Size2D<c_int>) -> crate::Result<()> {
+        self.set_size(size)
+    }
+
+    /// Move this window without changing its size. This is the same as `set_position`.
+    #[inline]
+    fn relocate(&self, pos: Point2D<c_int>) -> crate::Result<()> {
+        self.set_position(pos)
+    }
+
+    /// Bring this window to the top of the Z-order, activating it if it is a top-level
+    /// window.
+    #[inline]
+    fn bring_to_top(&self) -> crate::Result<()> {
+        if unsafe { winuser::BringWindowToTop(self.try_hwnd()?.as_mut()) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::BringWindowToTop))
         } else {
             Ok(())
         }
     }
 
-    /// Set the text value of this window.
+    /// Set whether or not this window stays above all non-topmost windows.
     #[inline]
-    fn set_text(&self, text: &str) -> crate::Result<()> {
-        // note: i've personally tested this in C. You can delete the actual
-        // allocated memory if you've already run SetWindowText.
-        if unsafe { winuser::SetWindowTextA(self.hwnd().as_mut(), text.as_ptr() as LPCSTR) } == 0 {
-            Err(crate::win32_error(crate::Win32Function::SetWindowTextA))
+    fn set_topmost(&self, topmost: bool) -> crate::Result<()> {
+        set_window_pos(
+            self.try_hwnd()?,
+            if topmost {
+                winuser::HWND_TOPMOST
+            } else {
+                winuser::HWND_NOTOPMOST
+            },
+            Rect::zero(),
+            SetWindowPosFlags::NO_MOVE | SetWindowPosFlags::NO_SIZE,
+        )
+    }
+
+    /// Bring this window to the foreground and activate it.
+    ///
+    /// `SetForegroundWindow` is ignored when the calling thread does not own the current
+    /// foreground window. This works around that by temporarily attaching this thread's
+    /// input to the foreground window's thread.
+    fn force_foreground(&self) -> crate::Result<()> {
+        let foreground = unsafe { winuser::GetForegroundWindow() };
+        let this_thread = unsafe { processthreadsapi::GetCurrentThreadId() };
+        let foreground_thread = if foreground.is_null() {
+            this_thread
+        } else {
+            unsafe { winuser::GetWindowThreadProcessId(foreground, ptr::null_mut()) }
+        };
+
+        let attach = foreground_thread != this_thread;
+        if attach
+            && unsafe { winuser::AttachThreadInput(this_thread, foreground_thread, TRUE) } == 0
+        {
+            return Err(crate::win32_error(crate::Win32Function::AttachThreadInput));
+        }
+
+        let res = if unsafe { winuser::SetForegroundWindow(self.try_hwnd()?.as_mut()) } == 0 {
+            Err(crate::win32_error(
+                crate::Win32Function::SetForegroundWindow,
+            ))
         } else {
             Ok(())
+        };
+
+        if attach {
+            unsafe { winuser::AttachThreadInput(this_thread, foreground_thread, FALSE) };
         }
+
+        res
     }
 
-    /// Invalid this window and force a redraw.
+    /// Get a copy of this window's region, or `None` if the window does not have one
+    /// (in which case it is rectangular).
     #[inline]
-    fn invalidate(&self, invalidated_rect: Option<Rect<c_int>>) -> crate::Result<()> {
-        let rect = invalidated_rect.map(crate::eurect_to_winrect);
-        if unsafe {
-            winuser::InvalidateRect(
-                self.hwnd().as_mut(),
-                match rect {
-                    Some(ref r) => r,
-                    None => ptr::null(),
-                },
-                TRUE,
-            )
-        } == 0
+    fn window_region(&self) -> crate::Result<Option<Region>> {
+        let region = Region::rect(Rect::zero())?;
+
+        unsafe { errhandlingapi::SetLastError(0) };
+        if unsafe { winuser::GetWindowRgn(self.try_hwnd()?.as_mut(), region.hrgn().as_mut()) }
+            == wingdi::ERROR
         {
-            Err(crate::win32_error(crate::Win32Function::InvalidateRect))
+            if unsafe { errhandlingapi::GetLastError() } == 0 {
+                Ok(None)
+            } else {
+                Err(crate::win32_error(crate::Win32Function::GetWindowRgn))
+            }
         } else {
-            Ok(())
+            Ok(Some(region))
         }
     }
 
-    /// Send a paint event to this window.
+    /// Set the opacity of this window, from 0 (fully transparent) to 255 (fully opaque).
+    /// Any color key set with `set_color_key` is kept.
+    ///
+    /// The window must have the `LAYERED` extended style for this to work.
     #[inline]
-    fn repaint(&self) {
-        unsafe { winuser::SendMessageA(self.hwnd().as_mut(), winuser::WM_PAINT, 0, 0) };
+    fn set_opacity(&self, alpha: u8) -> crate::Result<()> {
+        set_layered_attributes(self.try_hwnd()?, None, Some(alpha))
     }
 
-    /// Begin painting ops on this window.
+    /// Set the color key of this window. Pixels of this color are made fully
+    /// transparent. Any opacity set with `set_opacity` is kept.
+    ///
+    /// The window must have the `LAYERED` extended style for this to work.
     #[inline]
-    fn begin_paint(&self) -> crate::Result<DeviceContext> {
-        DeviceContext::begin_paint(self)
+    fn set_color_key(&self, r: u8, g: u8, b: u8) -> crate::Result<()> {
+        set_layered_attributes(self.try_hwnd()?, Some(wingdi::RGB(r, g, b)), None)
     }
-}
 
-#[inline]
-unsafe fn get_hwnd(a: &Arc<Mutex<AtomicPtr<HWND__>>>) -> NonNull<HWND__> {
-    let mut p = a.lock();
-    let ptr = p.get_mut();
-    debug_assert!(!ptr.is_null());
-    NonNull::new_unchecked(*ptr)
-}
+    /// Set the contents and per-pixel transparency of this window from a bitmap.
+    ///
+    /// The bitmap should be a 32-bit bitmap with premultiplied alpha, such as one made with
+    /// `Bitmap::from_rgba_straight`. `alpha` is applied on top of the bitmap's own alpha.
+    /// The window must have the `LAYERED` extended style, and `set_opacity` or
+    /// `set_color_key` must not have been called on it.
+    fn update_layered(&self, bitmap: &Bitmap, alpha: u8) -> crate::Result<()> {
+        let bitmap_size = bitmap.size();
+        let mut size = SIZE {
+            cx: bitmap_size.width,
+            cy: bitmap_size.height,
+        };
+        let mut src_origin = POINT { x: 0, y: 0 };
+        let mut blend = wingdi::BLENDFUNCTION {
+            BlendOp: wingdi::AC_SRC_OVER,
+            BlendFlags: 0,
+            SourceConstantAlpha: alpha,
+            AlphaFormat: wingdi::AC_SRC_ALPHA,
+        };
 
-impl GenericWindow for Window {
-    fn hwnd(&self) -> NonNull<HWND__> {
-        // TODO: this might be unsound. check it later.
-        unsafe { get_hwnd(&self.hwnd) }
+        if unsafe {
+            winuser::UpdateLayeredWindow(
+                self.try_hwnd()?.as_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut size,
+                bitmap.dc().hdc().as_mut(),
+                &mut src_origin,
+                0,
+                &mut blend,
+                winuser::ULW_ALPHA,
+            )
+        } == 0
+        {
+            Err(crate::win32_error(
+                crate::Win32Function::UpdateLayeredWindow,
+            ))
+        } else {
+            Ok(())
+        }
     }
 
-    fn weak_reference(&self) -> WeakWindow {
-        WeakWindow {
-            hwnd: Arc::downgrade(&self.hwnd),
+    /// Get the DPI of the monitor this window is on.
+    ///
+    /// On versions of Windows older than Windows 10 version 1607, this falls back to the
+    /// DPI of the window's device context.
+    fn dpi(&self) -> crate::Result<u32> {
+        if let Some(get_dpi_for_window) = user32_function(b"GetDpiForWindow\0") {
+            let get_dpi_for_window: unsafe extern "system" fn(HWND) -> UINT =
+                unsafe { mem::transmute(get_dpi_for_window) };
+            let dpi = unsafe { get_dpi_for_window(self.try_hwnd()?.as_mut()) };
+            if dpi != 0 {
+                return Ok(dpi);
+            }
         }
+
+        let mut hwnd = self.try_hwnd()?;
+        let hdc = unsafe { winuser::GetDC(hwnd.as_mut()) };
+        if hdc.is_null() {
+            return Err(crate::win32_error(crate::Win32Function::GetDC));
+        }
+
+        let dpi = unsafe { wingdi::GetDeviceCaps(hdc, wingdi::LOGPIXELSX) };
+        unsafe { winuser::ReleaseDC(hwnd.as_mut(), hdc) };
+        Ok(dpi as u32)
     }
-}
 
-impl GenericWindow for DroplessWindow {
-    fn hwnd(&self) -> NonNull<HWND__> {
-        unsafe { get_hwnd(&self.hwnd) }
+    /// Get the factor that this window's contents should be scaled by, where `1.0`
+    /// corresponds to 96 DPI.
+    #[inline]
+    fn scale_factor(&self) -> f64 {
+        f64::from(self.dpi().unwrap_or_else(|_| system_dpi())) / 96.0
     }
 
-    fn weak_reference(&self) -> WeakWindow {
-        WeakWindow {
-            hwnd: Arc::downgrade(&self.hwnd),
+    /// Give this window the keyboard focus.
+    #[inline]
+    fn set_focus(&self) -> crate::Result<()> {
+        // SetFocus returns the previously focused window, which may be null even if the
+        // call succeeded
+        unsafe { errhandlingapi::SetLastError(0) };
+        if unsafe { winuser::SetFocus(self.try_hwnd()?.as_mut()) }.is_null()
+            && unsafe { errhandlingapi::GetLastError() } != 0
+        {
+            Err(crate::win32_error(crate::Win32Function::SetFocus))
+        } else {
+            Ok(())
         }
     }
-}
 
-impl GenericWindow for WeakWindow {
-    fn hwnd(&self) -> NonNull<HWND__> {
-        let upgraded = self
-            .hwnd
-            .upgrade()
-            .expect("Unable to upgrade weak window into strong window.");
+    /// Enable or unenable this window.
+    #[inline]
+    fn enable(&self, do_display: bool) {
+        unsafe { winuser::EnableWindow(self.hwnd().as_mut(), crate::wboolify(do_display)) };
+    }
 
-        unsafe { get_hwnd(&upgraded) }
+    /// Disable this window until the returned guard is dropped. If the window was enabled
+    /// before, the guard enables it again, even if the code in between returns early or
+    /// panics.
+    #[inline]
+    fn disable_scoped(&self) -> EnableGuard {
+        let mut hwnd = self.hwnd();
+        let was_disabled = unsafe { winuser::EnableWindow(hwnd.as_mut(), FALSE) } != 0;
+        EnableGuard {
+            hwnd: Mutex::new(AtomicPtr::new(hwnd.as_ptr())),
+            was_disabled,
+        }
     }
 
-    fn weak_reference(&self) -> WeakWindow {
+    /// Tell whether or not this window is enabled.
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        unsafe { winuser::IsWindowEnabled(self.hwnd().as_mut()) != 0 }
+    }
+
+    /// Tell whether or not this window is visible.
+    #[inline]
+    fn is_visible(&self) -> bool {
+        unsafe { winuser::IsWindowVisible(self.hwnd().as_mut()) != 0 }
+    }
+
+    /// Tell whether or not this window is minimized.
+    #[inline]
+    fn is_minimized(&self) -> bool {
+        unsafe { winuser::IsIconic(self.hwnd().as_mut()) != 0 }
+    }
+
+    /// Tell whether or not this window is maximized.
+    #[inline]
+    fn is_maximized(&self) -> bool {
+        unsafe { winuser::IsZoomed(self.hwnd().as_mut()) != 0 }
+    }
+
+    /// Show the window.
+    #[inline]
+    fn show(&self, cmd_show: CmdShow) {
+        unsafe { winuser::ShowWindow(self.hwnd().as_mut(), cmd_show as c_int) };
+    }
+
+    /// Update the window.
+    #[inline]
+    fn update(&self) -> crate::Result<()> {
+        if unsafe { winuser::UpdateWindow(self.try_hwnd()?.as_mut()) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::UpdateWindow))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the text value of this window.
+    #[inline]
+    fn set_text(&self, text: &str) -> crate::Result<()> {
+        // note: i've personally tested this in C. You can delete the actual
+        // allocated memory if you've already run SetWindowText.
+        if unsafe { winuser::SetWindowTextA(self.try_hwnd()?.as_mut(), text.as_ptr() as LPCSTR) }
+            == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::SetWindowTextA))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the text of this window to a wide string. This avoids converting the text on
+    /// every call; for text that never changes, `constant_text!` builds the string at
+    /// compile time, so nothing is allocated at all.
+    #[inline]
+    fn set_text_wide(&self, text: &WStr) -> crate::Result<()> {
+        if unsafe { winuser::SetWindowTextW(self.try_hwnd()?.as_mut(), text.as_ptr()) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::SetWindowTextW))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Invalid this window and force a redraw.
+    #[inline]
+    fn invalidate(&self, invalidated_rect: Option<Rect<c_int>>) -> crate::Result<()> {
+        invalidate_rect(self.try_hwnd()?, invalidated_rect, true)
+    }
+
+    /// Invalidate this window without erasing its background first. If the paint handler
+    /// draws over the whole invalidated area anyway, this avoids the flicker of the
+    /// background showing for a moment.
+    #[inline]
+    fn invalidate_no_erase(&self, invalidated_rect: Option<Rect<c_int>>) -> crate::Result<()> {
+        invalidate_rect(self.try_hwnd()?, invalidated_rect, false)
+    }
+
+    /// Redraw part of this window with `RedrawWindow`, or the whole window if `rect` is
+    /// `None`. Unlike `invalidate`, the flags control whether the background is erased,
+    /// whether the frame and children are included, and whether painting happens
+    /// immediately with `UPDATE_NOW`.
+    fn redraw(&self, rect: Option<Rect<c_int>>, flags: RedrawFlags) -> crate::Result<()> {
+        let rect = rect.map(crate::eurect_to_winrect);
+        if unsafe {
+            winuser::RedrawWindow(
+                self.try_hwnd()?.as_mut(),
+                match rect {
+                    Some(ref r) => r,
+                    None => ptr::null(),
+                },
+                ptr::null_mut(),
+                flags.bits(),
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::RedrawWindow))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Start a timer that sends `WM_TIMER` to this window every `interval_ms`
+    /// milliseconds. The timer's ID is passed in the `wParam` of each `WM_TIMER`, so the
+    /// window procedure can tell its timers apart. If a timer with this ID already exists
+    /// on this window, it is replaced. Returns the ID of the timer.
+    #[inline]
+    fn set_timer(&self, id: usize, interval_ms: u32) -> crate::Result<usize> {
+        match unsafe { winuser::SetTimer(self.try_hwnd()?.as_mut(), id, interval_ms, None) } {
+            0 => Err(crate::win32_error(crate::Win32Function::SetTimer)),
+            id => Ok(id),
+        }
+    }
+
+    /// Stop a timer started with `set_timer`.
+    #[inline]
+    fn kill_timer(&self, id: usize) -> crate::Result<()> {
+        if unsafe { winuser::KillTimer(self.try_hwnd()?.as_mut(), id) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::KillTimer))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Capture the contents of this window's client area into a bitmap compatible with
+    /// `dc_factory`.
+    ///
+    /// This uses `PrintWindow` with `PW_RENDERFULLCONTENT`, which asks the window to draw
+    /// itself into the bitmap. Unlike copying pixels off of the screen, this works even if
+    /// the window is covered by other windows.
+    fn capture(&self, dc_factory: &DeviceContext) -> crate::Result<Bitmap> {
+        let size = self.client_rect()?.size;
+        let bitmap = Bitmap::dib_section(dc_factory, size)?;
+
+        if unsafe {
+            winuser::PrintWindow(
+                self.try_hwnd()?.as_mut(),
+                bitmap.dc().hdc().as_mut(),
+                winuser::PW_CLIENTONLY | winuser::PW_RENDERFULLCONTENT,
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::PrintWindow))
+        } else {
+            Ok(bitmap)
+        }
+    }
+
+    /// Subclass this window, running a closure on every message it receives before its
+    /// window procedure. If the closure returns `Some`, that value is returned from the
+    /// window procedure; if it returns `None`, the message is passed on as normal. The
+    /// subclass is removed when the returned guard is dropped, or when the window is
+    /// destroyed.
+    ///
+    /// Messages sent to the window from inside of the closure are not passed back into
+    /// it.
+    #[inline]
+    fn subclass<F>(&self, f: F) -> crate::Result<SubclassGuard>
+    where
+        Self: Sized,
+        F: FnMut(&DroplessWindow, UINT, WPARAM, LPARAM) -> Option<LRESULT> + 'static,
+    {
+        SubclassGuard::install(unsafe { self.try_hwnd()?.as_mut() }, Box::new(f))
+    }
+
+    /// Send a buffer of bytes to another window, possibly in another process, with
+    /// `WM_COPYDATA`. `id` is passed along with the data, to tell different kinds of data
+    /// apart. Returns whether or not the target window processed the message.
+    fn send_copy_data(
+        &self,
+        target: &dyn GenericWindow,
+        id: usize,
+        data: &[u8],
+    ) -> crate::Result<bool> {
+        if data.len() > DWORD::MAX as usize {
+            return Err(crate::Error::StaticMsg(
+                "Data is too large to send with WM_COPYDATA",
+            ));
+        }
+
+        let copy_data = COPYDATASTRUCT {
+            dwData: id,
+            cbData: data.len() as DWORD,
+            lpData: data.as_ptr() as *mut c_void,
+        };
+
+        // the data only needs to live until SendMessage returns
+        Ok(unsafe {
+            winuser::SendMessageA(
+                target.try_hwnd()?.as_mut(),
+                winuser::WM_COPYDATA,
+                self.try_hwnd()?.as_ptr() as WPARAM,
+                &copy_data as *const COPYDATASTRUCT as LPARAM,
+            )
+        } != 0)
+    }
+
+    /// Get the menu bar of this window, if it has one. The menu still belongs to the
+    /// window, and is not destroyed when the returned `MenuRef` is dropped. It should not
+    /// be used after the window's menu is replaced with `set_menu`.
+    #[inline]
+    fn menu(&self) -> Option<MenuRef<'_>> {
+        NonNull::new(unsafe { winuser::GetMenu(self.hwnd().as_mut()) }).map(MenuRef::new)
+    }
+
+    /// Get the path of the module (the executable or DLL) that created this window.
+    ///
+    /// This only works for windows created by the current process. For a window in
+    /// another process, Windows either fails or returns a path from this process instead.
+    /// To find the executable of another process, get its process ID with
+    /// `GetWindowThreadProcessId` and pass it to `QueryFullProcessImageName`.
+    fn module_file_name(&self) -> crate::Result<String> {
+        let mut hwnd = self.try_hwnd()?;
+        let mut buffer: Vec<WCHAR> = vec![0; MAX_PATH];
+
+        loop {
+            unsafe { errhandlingapi::SetLastError(0) };
+            let len = unsafe {
+                winuser::GetWindowModuleFileNameW(
+                    hwnd.as_mut(),
+                    buffer.as_mut_ptr(),
+                    buffer.len() as UINT,
+                )
+            } as usize;
+
+            if len == 0 && unsafe { errhandlingapi::GetLastError() } != 0 {
+                return Err(crate::win32_error(
+                    crate::Win32Function::GetWindowModuleFileNameW,
+                ));
+            } else if len < buffer.len() {
+                return Ok(String::from_utf16(&buffer[..len])?);
+            }
+
+            // the path was cut off; try again with a bigger buffer
+            let new_len = buffer.len() * 2;
+            buffer.resize(new_len, 0);
+        }
+    }
+
+    /// Get the parent of this window, or `None` if this is a top-level window. For a
+    /// top-level window with an owner, such as a dialog box, this is the owner instead.
+    fn parent(&self) -> crate::Result<Option<DroplessWindow>> {
+        let mut hwnd = self.try_hwnd()?;
+        unsafe { errhandlingapi::SetLastError(0) };
+        let parent = unsafe { winuser::GetParent(hwnd.as_mut()) };
+
+        if !parent.is_null() {
+            Ok(Some(unsafe { DroplessWindow::new(parent) }))
+        } else if unsafe { errhandlingapi::GetLastError() } == 0 {
+            Ok(None)
+        } else {
+            Err(crate::win32_error(crate::Win32Function::GetParent))
+        }
+    }
+
+    /// Move this window into a new parent window, or make it a top-level window if
+    /// `new_parent` is `None`. The `CHILD` and `POPUP` styles are not updated to match.
+    fn set_parent(&self, new_parent: Option<&dyn GenericWindow>) -> crate::Result<()> {
+        let new_parent = match new_parent {
+            Some(new_parent) => unsafe { new_parent.try_hwnd()?.as_mut() },
+            None => ptr::null_mut(),
+        };
+
+        unsafe { errhandlingapi::SetLastError(0) };
+        if unsafe { winuser::SetParent(self.try_hwnd()?.as_mut(), new_parent) }.is_null()
+            && unsafe { errhandlingapi::GetLastError() } != 0
+        {
+            Err(crate::win32_error(crate::Win32Function::SetParent))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the top-level window that contains this window, by walking up the chain of
+    /// parents. A top-level window is its own root.
+    fn root(&self) -> DroplessWindow {
+        let mut hwnd = self.hwnd();
+        let root = unsafe { winuser::GetAncestor(hwnd.as_mut(), winuser::GA_ROOT) };
+        unsafe { DroplessWindow::new(if root.is_null() { hwnd.as_ptr() } else { root }) }
+    }
+
+    /// Get every child window of this window, including children of children.
+    fn enum_children(&self) -> crate::Result<Vec<DroplessWindow>> {
+        let mut state = EnumState::default();
+        unsafe {
+            winuser::EnumChildWindows(
+                self.try_hwnd()?.as_mut(),
+                Some(enum_trampoline),
+                &mut state as *mut EnumState as LPARAM,
+            )
+        };
+        state.into_windows()
+    }
+
+    /// Destroy this window.
+    ///
+    /// This is the normal way to close a window from inside of its window procedure,
+    /// usually by calling it on a `DroplessWindow` in response to `WM_CLOSE`. If this is
+    /// called on a `Window` that holds boxed user data, take the user data out first;
+    /// otherwise, it can no longer be retrieved and is leaked when the `Window` is
+    /// dropped.
+    #[inline]
+    fn destroy(&self) -> crate::Result<()> {
+        if unsafe { winuser::DestroyWindow(self.try_hwnd()?.as_mut()) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::DestroyWindow))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Validate this window, removing a rectangle (or the entire window, if `None`) from
+    /// its update region.
+    #[inline]
+    fn validate(&self, validated_rect: Option<Rect<c_int>>) -> crate::Result<()> {
+        let rect = validated_rect.map(crate::eurect_to_winrect);
+        if unsafe {
+            winuser::ValidateRect(
+                self.try_hwnd()?.as_mut(),
+                match rect {
+                    Some(ref r) => r,
+                    None => ptr::null(),
+                },
+            )
+        } == 0
+        {
+            Err(crate::win32_error(crate::Win32Function::ValidateRect))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Send a paint event to this window.
+    #[inline]
+    fn repaint(&self) {
+        unsafe { winuser::SendMessageA(self.hwnd().as_mut(), winuser::WM_PAINT, 0, 0) };
+    }
+
+    /// Begin painting ops on this window.
+    ///
+    /// A `WM_PAINT` handler must either call this or `validate` before returning.
+    /// Otherwise, the window's update region stays invalid and Windows will keep sending
+    /// `WM_PAINT`. `dispatch_message` validates the window as a fallback.
+    #[inline]
+    fn begin_paint(&self) -> crate::Result<DeviceContext> {
+        DeviceContext::begin_paint(self)
+    }
+}
+
+// call SetWindowPos on a window
+fn set_window_pos(
+    mut hwnd: NonNull<HWND__>,
+    insert_after: HWND,
+    bounds: Rect<c_int>,
+    flags: SetWindowPosFlags,
+) -> crate::Result<()> {
+    if unsafe {
+        winuser::SetWindowPos(
+            hwnd.as_mut(),
+            insert_after,
+            bounds.origin.x,
+            bounds.origin.y,
+            bounds.size.width,
+            bounds.size.height,
+            flags.bits(),
+        )
+    } == 0
+    {
+        Err(crate::win32_error(crate::Win32Function::SetWindowPos))
+    } else {
+        Ok(())
+    }
+}
+
+// call InvalidateRect on a window
+fn invalidate_rect(
+    mut hwnd: NonNull<HWND__>,
+    rect: Option<Rect<c_int>>,
+    erase: bool,
+) -> crate::Result<()> {
+    let rect = rect.map(crate::eurect_to_winrect);
+    if unsafe {
+        winuser::InvalidateRect(
+            hwnd.as_mut(),
+            match rect {
+                Some(ref r) => r,
+                None => ptr::null(),
+            },
+            crate::wboolify(erase),
+        )
+    } == 0
+    {
+        Err(crate::win32_error(crate::Win32Function::InvalidateRect))
+    } else {
+        Ok(())
+    }
+}
+
+// call SetLayeredWindowAttributes on a window, changing either the color key or the
+// opacity while keeping the other one
+fn set_layered_attributes(
+    mut hwnd: NonNull<HWND__>,
+    key: Option<COLORREF>,
+    alpha: Option<u8>,
+) -> crate::Result<()> {
+    let mut old_key: COLORREF = 0;
+    let mut old_alpha: u8 = 0xFF;
+    let mut old_flags: DWORD = 0;
+
+    // this fails if the attributes were never set, in which case there's nothing to keep
+    if unsafe {
+        winuser::GetLayeredWindowAttributes(
+            hwnd.as_mut(),
+            &mut old_key,
+            &mut old_alpha,
+            &mut old_flags,
+        )
+    } == 0
+    {
+        old_flags = 0;
+    }
+
+    let mut flags = old_flags & (winuser::LWA_COLORKEY | winuser::LWA_ALPHA);
+    let key = match key {
+        Some(key) => {
+            flags |= winuser::LWA_COLORKEY;
+            key
+        }
+        None => old_key,
+    };
+    let alpha = match alpha {
+        Some(alpha) => {
+            flags |= winuser::LWA_ALPHA;
+            alpha
+        }
+        None if flags & winuser::LWA_ALPHA != 0 => old_alpha,
+        None => 0xFF,
+    };
+
+    if unsafe { winuser::SetLayeredWindowAttributes(hwnd.as_mut(), key, alpha, flags) } == 0 {
+        Err(crate::win32_error(
+            crate::Win32Function::SetLayeredWindowAttributes,
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Get the window that currently has the keyboard focus, if it is attached to the
+/// calling thread's message queue.
+#[inline]
+pub fn focused_window() -> Option<DroplessWindow> {
+    let hwnd = unsafe { winuser::GetFocus() };
+    if hwnd.is_null() {
+        None
+    } else {
+        Some(unsafe { DroplessWindow::new(hwnd) })
+    }
+}
+
+/// Find a top-level window by its class name, its title, or both. A `None` argument
+/// matches any window. Returns `Ok(None)` if no window matches.
+pub fn find_window(
+    class_name: Option<&str>,
+    window_name: Option<&str>,
+) -> crate::Result<Option<DroplessWindow>> {
+    let class_name = class_name.map(WString::from);
+    let window_name = window_name.map(WString::from);
+
+    unsafe { errhandlingapi::SetLastError(0) };
+    let hwnd = unsafe {
+        winuser::FindWindowW(
+            class_name.as_ref().map_or(ptr::null(), |c| c.as_ptr()),
+            window_name.as_ref().map_or(ptr::null(), |w| w.as_ptr()),
+        )
+    };
+
+    if !hwnd.is_null() {
+        Ok(Some(unsafe { DroplessWindow::new(hwnd) }))
+    } else if unsafe { errhandlingapi::GetLastError() } == 0 {
+        Ok(None)
+    } else {
+        Err(crate::win32_error(crate::Win32Function::FindWindowW))
+    }
+}
+
+/// Get every top-level window on the screen, in Z order from top to bottom.
+pub fn enum_top_level_windows() -> crate::Result<Vec<DroplessWindow>> {
+    let mut state = EnumState::default();
+    if unsafe {
+        winuser::EnumWindows(
+            Some(enum_trampoline),
+            &mut state as *mut EnumState as LPARAM,
+        )
+    } == 0
+        && !state.panicked
+    {
+        Err(crate::win32_error(crate::Win32Function::EnumWindows))
+    } else {
+        state.into_windows()
+    }
+}
+
+// the windows collected by enum_trampoline
+#[derive(Default)]
+struct EnumState {
+    windows: Vec<HWND>,
+    panicked: bool,
+}
+
+impl EnumState {
+    fn into_windows(self) -> crate::Result<Vec<DroplessWindow>> {
+        if self.panicked {
+            Err(crate::Error::StaticMsg(
+                "Panicked while enumerating windows",
+            ))
+        } else {
+            Ok(self
+                .windows
+                .into_iter()
+                .map(|hwnd| unsafe { DroplessWindow::new(hwnd) })
+                .collect())
+        }
+    }
+}
+
+// collect each window into the EnumState passed through the lparam. unwinding across the
+// FFI boundary is undefined behavior, so a panic stops the enumeration instead.
+unsafe extern "system" fn enum_trampoline(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let state = &mut *(lparam as *mut EnumState);
+
+    #[cfg(feature = "std")]
+    let pushed =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| state.windows.push(hwnd))).is_ok();
+    #[cfg(not(feature = "std"))]
+    let pushed = {
+        state.windows.push(hwnd);
+        true
+    };
+
+    if pushed {
+        TRUE
+    } else {
+        state.panicked = true;
+        FALSE
+    }
+}
+
+// look up a function in user32.dll that may not exist on older versions of Windows
+fn user32_function(name: &'static [u8]) -> Option<FARPROC> {
+    debug_assert_eq!(name.last(), Some(&0));
+
+    let user32 = unsafe { libloaderapi::GetModuleHandleA(b"user32.dll\0".as_ptr() as LPCSTR) };
+    if user32.is_null() {
+        return None;
+    }
+
+    let f = unsafe { libloaderapi::GetProcAddress(user32, name.as_ptr() as LPCSTR) };
+    if f.is_null() {
+        None
+    } else {
+        Some(f)
+    }
+}
+
+/// Get the DPI of the system.
+///
+/// On versions of Windows older than Windows 10 version 1607, this falls back to the
+/// DPI of the screen's device context.
+pub fn system_dpi() -> u32 {
+    if let Some(get_dpi_for_system) = user32_function(b"GetDpiForSystem\0") {
+        let get_dpi_for_system: unsafe extern "system" fn() -> UINT =
+            unsafe { mem::transmute(get_dpi_for_system) };
+        return unsafe { get_dpi_for_system() };
+    }
+
+    let hdc = unsafe { winuser::GetDC(ptr::null_mut()) };
+    if hdc.is_null() {
+        return 96;
+    }
+
+    let dpi = unsafe { wingdi::GetDeviceCaps(hdc, wingdi::LOGPIXELSX) };
+    unsafe { winuser::ReleaseDC(ptr::null_mut(), hdc) };
+    dpi as u32
+}
+
+#[inline]
+unsafe fn get_hwnd(a: &Arc<Mutex<AtomicPtr<HWND__>>>) -> NonNull<HWND__> {
+    let mut p = a.lock();
+    let ptr = p.get_mut();
+    debug_assert!(!ptr.is_null());
+    NonNull::new_unchecked(*ptr)
+}
+
+impl GenericWindow for Window {
+    fn hwnd(&self) -> NonNull<HWND__> {
+        // TODO: this might be unsound. check it later.
+        unsafe { get_hwnd(&self.hwnd) }
+    }
+
+    fn weak_reference(&self) -> WeakWindow {
+        WeakWindow {
+            hwnd: Arc::downgrade(&self.hwnd),
+        }
+    }
+}
+
+impl GenericWindow for DroplessWindow {
+    fn hwnd(&self) -> NonNull<HWND__> {
+        unsafe { get_hwnd(&self.hwnd) }
+    }
+
+    fn weak_reference(&self) -> WeakWindow {
+        WeakWindow {
+            hwnd: Arc::downgrade(&self.hwnd),
+        }
+    }
+}
+
+impl GenericWindow for WeakWindow {
+    fn hwnd(&self) -> NonNull<HWND__> {
+        self.try_hwnd()
+            .expect("Unable to upgrade weak window into strong window.")
+    }
+
+    fn try_hwnd(&self) -> crate::Result<NonNull<HWND__>> {
+        let upgraded = self.hwnd.upgrade().ok_or(crate::Error::ExpiredWeakPtr)?;
+        Ok(unsafe { get_hwnd(&upgraded) })
+    }
+
+    fn weak_reference(&self) -> WeakWindow {
         self.clone()
     }
 }
@@ -496,33 +1601,58 @@ impl Window {
         parent: Option<&Self>,
         create_parameter: Option<Box<T>>,
     ) -> crate::Result<Self> {
-        let parent = match parent {
-            Some(p) => unsafe { p.hwnd().as_mut() },
-            None => ptr::null_mut(),
-        };
-
         let lpparam = match create_parameter {
             Some(c) => Box::into_raw(c),
             None => ptr::null_mut(),
         };
 
-        let hwnd = unsafe {
-            winuser::CreateWindowExA(
-                extended_style.bits(),
-                window_class.identifier().as_ptr() as LPCSTR,
-                window_name.as_ptr() as LPCSTR,
-                style.bits(),
-                bounds.origin.x,
-                bounds.origin.y,
-                bounds.size.width,
-                bounds.size.height,
+        unsafe {
+            Self::with_raw_creation_param(
+                window_class,
+                window_name,
+                style,
+                extended_style,
+                bounds,
                 parent,
-                ptr::null_mut(),
-                crate::MODULE_INFO.lock().handle().as_mut(),
                 lpparam as *mut c_void,
             )
+        }
+    }
+
+    // create a new window, passing a raw pointer as the creation parameter
+    pub(crate) unsafe fn with_raw_creation_param<WC: WindowClass>(
+        window_class: &WC,
+        window_name: &str,
+        style: WindowStyle,
+        extended_style: ExtendedWindowStyle,
+        bounds: Rect<c_int>,
+        parent: Option<&Self>,
+        lpparam: *mut c_void,
+    ) -> crate::Result<Self> {
+        style
+            .validate(parent.is_some())
+            .map_err(crate::Error::StaticMsg)?;
+
+        let parent = match parent {
+            Some(p) => p.try_hwnd()?.as_mut(),
+            None => ptr::null_mut(),
         };
 
+        let hwnd = winuser::CreateWindowExA(
+            extended_style.bits(),
+            window_class.identifier().as_ptr() as LPCSTR,
+            window_name.as_ptr() as LPCSTR,
+            style.bits(),
+            bounds.origin.x,
+            bounds.origin.y,
+            bounds.size.width,
+            bounds.size.height,
+            parent,
+            ptr::null_mut(),
+            crate::module_instance().as_ptr(),
+            lpparam,
+        );
+
         if hwnd.is_null() {
             Err(crate::win32_error(crate::Win32Function::CreateWindowExA))
         } else {
@@ -554,6 +1684,74 @@ impl Window {
         )
     }
 
+    /// Create a child control of a system class, such as `"BUTTON"` or `"EDIT"`. The
+    /// control ID is sent to the parent in `WM_COMMAND` notifications, which is how the
+    /// parent tells its controls apart. `control_style` holds the class-specific style
+    /// bits (such as `BS_PUSHBUTTON`), which are combined with `style`.
+    pub fn new_control(
+        class_name: &str,
+        text: &str,
+        style: WindowStyle,
+        control_style: DWORD,
+        bounds: Rect<c_int>,
+        parent: &dyn GenericWindow,
+        id: u16,
+    ) -> crate::Result<Self> {
+        let style = style | WindowStyle::CHILD;
+        style.validate(true).map_err(crate::Error::StaticMsg)?;
+
+        let class_name = WString::from(class_name);
+        let text = WString::from(text);
+
+        let hwnd = unsafe {
+            winuser::CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                text.as_ptr(),
+                style.bits() | control_style,
+                bounds.origin.x,
+                bounds.origin.y,
+                bounds.size.width,
+                bounds.size.height,
+                parent.try_hwnd()?.as_mut(),
+                // for child windows, the menu handle is the control ID
+                id as usize as HMENU,
+                crate::module_instance().as_ptr(),
+                ptr::null_mut(),
+            )
+        };
+
+        if hwnd.is_null() {
+            Err(crate::win32_error(crate::Win32Function::CreateWindowExW))
+        } else {
+            Ok(Self {
+                hwnd: Arc::new(Mutex::new(AtomicPtr::new(hwnd))),
+                has_user_data: false,
+            })
+        }
+    }
+
+    /// Set the menu bar of this window. The window takes ownership of the menu, and it
+    /// is destroyed along with the window. The previous menu, if any, is destroyed.
+    pub fn set_menu(&self, menu: Menu) -> crate::Result<()> {
+        let mut hwnd = self.try_hwnd()?;
+        let old_menu = unsafe { winuser::GetMenu(hwnd.as_mut()) };
+
+        let mut menu_handle = unsafe { menu.hmenu() };
+        if unsafe { winuser::SetMenu(hwnd.as_mut(), menu_handle.as_mut()) } == 0 {
+            Err(crate::win32_error(crate::Win32Function::SetMenu))
+        } else {
+            // the window now owns the menu
+            mem::forget(menu);
+
+            if !old_menu.is_null() && old_menu != menu_handle.as_ptr() {
+                unsafe { winuser::DestroyMenu(old_menu) };
+            }
+
+            Ok(())
+        }
+    }
+
     /// Set the user data field of this window to a pointer.
     ///
     /// Note: This does not set has_user_data because we don't know the nature of the pointer.
@@ -575,6 +1773,9 @@ impl Window {
     }
 
     /// Set the user data of this window to a box.
+    ///
+    /// This must not be used on windows created with `create_handler_window`, whose user
+    /// data holds the window's handler.
     #[inline]
     pub fn set_user_data_box<T: ?Sized>(&mut self, b: Box<T>) -> crate::Result<()> {
         unsafe { self.set_user_data_pointer(Box::into_raw(b)) }?;
@@ -583,6 +1784,9 @@ impl Window {
     }
 
     /// Get the user data of this window.
+    ///
+    /// This must not be used on windows created with `create_handler_window`, whose user
+    /// data holds the window's handler.
     #[inline]
     pub fn user_data<T: Any>(&self) -> crate::Result<&T> {
         let res =
@@ -596,7 +1800,27 @@ impl Window {
         Ok(unsafe { &*res })
     }
 
+    /// Get the user data of this window mutably.
+    ///
+    /// This must not be used on windows created with `create_handler_window`, whose user
+    /// data holds the window's handler.
+    #[inline]
+    pub fn user_data_mut<T: Any>(&mut self) -> crate::Result<&mut T> {
+        let res =
+            unsafe { winuser::GetWindowLongPtrA(self.hwnd().as_mut(), winuser::GWLP_USERDATA) };
+
+        if res == FALSE as LONG_PTR {
+            return Err(crate::win32_error(crate::Win32Function::GetWindowLongPtrA));
+        }
+
+        let res = unsafe { mem::transmute::<LONG_PTR, *mut T>(res) };
+        Ok(unsafe { &mut *res })
+    }
+
     /// Take the user data of this window out.
+    ///
+    /// This must not be used on windows created with `create_handler_window`, whose user
+    /// data holds the window's handler.
     #[inline]
     pub fn take_user_data<T: Any>(&mut self) -> crate::Result<Box<T>> {
         unsafe { errhandlingapi::SetLastError(0) };
@@ -622,6 +1846,66 @@ impl Window {
     }
 }
 
+/// A container holding several pieces of user data, keyed by their type.
+///
+/// A window only has a single slot for user data. To store multiple independent values
+/// in a window, put them in a `UserData`, store it with `Window::set_user_data_box`,
+/// and retrieve it with `Window::user_data` or `Window::user_data_mut`.
+#[derive(Default)]
+pub struct UserData {
+    values: BTreeMap<TypeId, Box<dyn Any>>,
+}
+
+impl fmt::Debug for UserData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UserData")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+impl UserData {
+    /// Create a new, empty user data container.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value into the container, returning the previous value of that type.
+    #[inline]
+    pub fn insert<T: Any>(&mut self, value: T) -> Option<T> {
+        self.values
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|b| b.downcast::<T>().ok())
+            .map(|b| *b)
+    }
+
+    /// Get a reference to the value of a certain type.
+    #[inline]
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|b| b.downcast_ref::<T>())
+    }
+
+    /// Get a mutable reference to the value of a certain type.
+    #[inline]
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.values
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|b| b.downcast_mut::<T>())
+    }
+
+    /// Remove the value of a certain type from the container.
+    #[inline]
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        self.values
+            .remove(&TypeId::of::<T>())
+            .and_then(|b| b.downcast::<T>().ok())
+            .map(|b| *b)
+    }
+}
+
 impl DroplessWindow {
     /// Create a new dropless window.
     ///
@@ -633,6 +1917,48 @@ impl DroplessWindow {
             hwnd: Arc::new(Mutex::new(AtomicPtr::new(hwnd))),
         }
     }
+
+    /// Get the instance pointer stored in this window's user data field, or `None` if it
+    /// has not been set.
+    ///
+    /// This is meant for window procedures that store a pointer to their state during
+    /// `WM_NCCREATE` (usually taken from the `CREATESTRUCT`) and read it back for every
+    /// later message. The pointer is not checked to actually point to a `T`.
+    #[inline]
+    pub fn instance_ptr<T>(&self) -> Option<*mut T> {
+        let ptr =
+            unsafe { winuser::GetWindowLongPtrA(self.hwnd().as_mut(), winuser::GWLP_USERDATA) };
+        if ptr == 0 {
+            None
+        } else {
+            Some(ptr as *mut T)
+        }
+    }
+
+    /// Store an instance pointer in this window's user data field, to be retrieved later
+    /// with `instance_ptr`.
+    ///
+    /// # Safety
+    ///
+    /// The user data field is shared with `Window`'s user data. If this window is also
+    /// owned by a `Window` holding boxed user data, that `Window` will try to free this
+    /// pointer as its box when it is dropped.
+    #[inline]
+    pub unsafe fn set_instance_ptr<T>(&self, ptr: *mut T) -> crate::Result<()> {
+        errhandlingapi::SetLastError(0);
+
+        if winuser::SetWindowLongPtrA(
+            self.hwnd().as_mut(),
+            winuser::GWLP_USERDATA,
+            ptr as LONG_PTR,
+        ) == 0
+            && errhandlingapi::GetLastError() != 0
+        {
+            Err(crate::win32_error(crate::Win32Function::SetWindowLongPtrA))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl Drop for Window {
@@ -645,7 +1971,28 @@ impl Drop for Window {
                     winuser::GWLP_USERDATA,
                 )) as *mut dyn Any
             };
-            let _b = unsafe { Box::from_raw(pointer) }; // drops the box
+
+            // the pointer is null if the window has already been destroyed
+            if !pointer.is_null() {
+                let _b = unsafe { Box::from_raw(pointer) }; // drops the box
+            }
+        }
+    }
+}
+
+/// A guard that enables a window again when dropped, created by
+/// `GenericWindow::disable_scoped`.
+pub struct EnableGuard {
+    // the raw handle is kept, since a DroplessWindow's weak reference dies with it
+    hwnd: Mutex<AtomicPtr<HWND__>>,
+    was_disabled: bool,
+}
+
+impl Drop for EnableGuard {
+    fn drop(&mut self) {
+        // if the window has been destroyed in the meantime, this fails harmlessly
+        if !self.was_disabled {
+            unsafe { winuser::EnableWindow(*self.hwnd.lock().get_mut(), TRUE) };
         }
     }
 }
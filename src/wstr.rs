@@ -0,0 +1,412 @@
+/* -----------------------------------------------------------------------------------
+ * src/wstr.rs - Wide strings for use with the Unicode Win32 API.
+ * porcupine - Safe wrapper around the graphical parts of Win32.
+ * Copyright © 2020 not_a_seagull
+ *
+ * This project is licensed under either the Apache 2.0 license or the MIT license, at
+ * your option. For more information, please consult the LICENSE-APACHE or LICENSE-MIT
+ * files in the repository root.
+ * -----------------------------------------------------------------------------------
+ * MIT License:
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the “Software”), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * -----------------------------------------------------------------------------------
+ * Apache 2.0 License Declaration:
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ----------------------------------------------------------------------------------
+ */
+
+//! Wide strings, for use with the W-suffixed Win32 functions.
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt,
+    ops::{Add, Deref, Range},
+};
+use winapi::shared::ntdef::{LPCWSTR, WCHAR};
+
+/// A borrowed, NUL-terminated string of UTF-16 code units.
+#[repr(transparent)]
+pub struct WStr {
+    inner: [WCHAR],
+}
+
+impl WStr {
+    /// Create a wide string from a slice of code units. The slice must end with a NUL
+    /// terminator, or else `None` is returned.
+    #[inline]
+    pub fn from_bytes(units: &[WCHAR]) -> Option<&WStr> {
+        match units.last() {
+            Some(0) => Some(unsafe { Self::from_bytes_unchecked(units) }),
+            _ => None,
+        }
+    }
+
+    /// Create a wide string from a slice of code units without checking for the NUL
+    /// terminator.
+    ///
+    /// # Safety
+    ///
+    /// The slice must end with a NUL terminator.
+    #[inline]
+    pub unsafe fn from_bytes_unchecked(units: &[WCHAR]) -> &WStr {
+        &*(units as *const [WCHAR] as *const WStr)
+    }
+
+    /// Get a pointer to this string, suitable for passing into Win32 as an `LPCWSTR`.
+    #[inline]
+    pub fn as_ptr(&self) -> LPCWSTR {
+        self.inner.as_ptr()
+    }
+
+    /// Get the code units of this string, not including the NUL terminator.
+    #[inline]
+    pub fn as_slice(&self) -> &[WCHAR] {
+        &self.inner[..self.inner.len() - 1]
+    }
+
+    /// Get the number of code units in this string, not including the NUL terminator.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len() - 1
+    }
+
+    /// Tell whether or not this string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the code units of this string, including the NUL terminator.
+    #[inline]
+    pub fn as_slice_with_nul(&self) -> &[WCHAR] {
+        &self.inner
+    }
+
+    /// Get a range of code units out of this string, not including the NUL terminator.
+    /// Returns `None` if the range is out of bounds, or if either end of it falls in the
+    /// middle of a surrogate pair.
+    ///
+    /// The result is a plain slice rather than a `WStr`, since it is not NUL-terminated
+    /// and cannot be passed to Win32 as-is. Use `WString::from_vec` to get a terminated
+    /// copy.
+    pub fn slice(&self, range: Range<usize>) -> Option<&[WCHAR]> {
+        let units = self.as_slice();
+        if range.start > range.end || range.end > units.len() {
+            return None;
+        }
+
+        // a cut between a high and a low surrogate splits a pair
+        let splits_pair = |i: usize| {
+            i > 0
+                && i < units.len()
+                && (0xD800..0xDC00).contains(&units[i - 1])
+                && (0xDC00..0xE000).contains(&units[i])
+        };
+
+        if splits_pair(range.start) || splits_pair(range.end) {
+            None
+        } else {
+            Some(&units[range])
+        }
+    }
+
+    /// Convert this wide string into a Rust string.
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    pub fn into_string(&self) -> crate::Result<String> {
+        Ok(String::from_utf16(self.as_slice())?)
+    }
+
+    /// Convert this wide string into a Rust string, replacing invalid data with the
+    /// replacement character.
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    pub fn into_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_slice())
+    }
+}
+
+impl fmt::Display for WStr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.into_string_lossy())
+    }
+}
+
+impl fmt::Debug for WStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.into_string()?, f)
+    }
+}
+
+/// An owned, NUL-terminated string of UTF-16 code units.
+#[derive(Clone)]
+pub struct WString {
+    inner: Vec<WCHAR>,
+}
+
+impl WString {
+    /// Create a wide string from a vector of code units, appending a NUL terminator if
+    /// one is not already present.
+    #[inline]
+    pub fn from_vec(mut v: Vec<WCHAR>) -> Self {
+        if v.last() != Some(&0) {
+            v.push(0);
+        }
+
+        Self { inner: v }
+    }
+
+    /// Create a wide string from a vector of code units without checking for the NUL
+    /// terminator.
+    ///
+    /// # Safety
+    ///
+    /// The vector must end with a NUL terminator.
+    #[inline]
+    pub unsafe fn from_vec_unchecked(v: Vec<WCHAR>) -> Self {
+        debug_assert_eq!(v.last(), Some(&0));
+        Self { inner: v }
+    }
+
+    /// Create a wide string from an OS string, such as a file path.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn from_os_str(s: &std::ffi::OsStr) -> Self {
+        use std::os::windows::ffi::OsStrExt;
+
+        let v = s.encode_wide().chain(Some(0)).collect();
+        unsafe { Self::from_vec_unchecked(v) }
+    }
+
+    // add code units to the end of this string, before the NUL terminator
+    fn extend_units<I: IntoIterator<Item = WCHAR>>(&mut self, units: I) {
+        let nul = self.inner.pop();
+        debug_assert_eq!(nul, Some(0));
+        self.inner.extend(units);
+        self.inner.push(0);
+    }
+
+    /// Append a string to the end of this string.
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        self.inner.reserve(s.len());
+        self.extend_units(s.encode_utf16());
+    }
+
+    /// Append a character to the end of this string.
+    #[inline]
+    pub fn push(&mut self, c: char) {
+        let mut buffer = [0; 2];
+        self.extend_units(c.encode_utf16(&mut buffer).iter().copied());
+    }
+}
+
+impl From<&str> for WString {
+    #[inline]
+    fn from(s: &str) -> Self {
+        // reserve one extra spot for the NUL terminator
+        let mut v = Vec::with_capacity(s.len() + 1);
+        v.extend(s.encode_utf16());
+        v.push(0);
+        unsafe { Self::from_vec_unchecked(v) }
+    }
+}
+
+impl Add<&WStr> for WString {
+    type Output = WString;
+
+    #[inline]
+    fn add(mut self, other: &WStr) -> WString {
+        self.extend_units(other.as_slice().iter().copied());
+        self
+    }
+}
+
+impl Deref for WString {
+    type Target = WStr;
+
+    #[inline]
+    fn deref(&self) -> &WStr {
+        unsafe { WStr::from_bytes_unchecked(&self.inner) }
+    }
+}
+
+impl fmt::Display for WString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl fmt::Debug for WString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Create a `&'static WStr` from a string constant at compile time. The result is
+/// NUL-terminated, so it can be passed straight to Win32 as an `LPCWSTR`.
+///
+/// The argument is an ordinary `&str` constant, so escape sequences such as `\n`, `\"`
+/// and `\u{...}` are handled by the compiler, and `constant_text!("a\nb")` contains a
+/// real newline. The text can't contain NUL characters, since Win32 would stop reading
+/// at the first one; this is checked at compile time.
+#[macro_export]
+macro_rules! constant_text {
+    ($text:expr) => {{
+        const TEXT: &str = $text;
+        static UNITS: [$crate::winapi::shared::ntdef::WCHAR; $crate::wstr::utf16_len(TEXT) + 1] =
+            $crate::wstr::encode_utf16(TEXT);
+        unsafe { $crate::WStr::from_bytes_unchecked(&UNITS) }
+    }};
+}
+
+// the number of UTF-16 code units needed to encode a string, used by constant_text!
+#[doc(hidden)]
+pub const fn utf16_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    let mut len = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        // four-byte sequences are outside of the BMP, and need a surrogate pair
+        if b >= 0xF0 {
+            len += 2;
+            i += 4;
+        } else if b >= 0xE0 {
+            len += 1;
+            i += 3;
+        } else if b >= 0xC0 {
+            len += 1;
+            i += 2;
+        } else {
+            len += 1;
+            i += 1;
+        }
+    }
+
+    len
+}
+
+// encode a string as UTF-16 at compile time, used by constant_text!. any code units past
+// the end of the string are left as zero, which leaves room for the NUL terminator.
+// interior NULs are rejected, which is a compile error when this is evaluated for a static.
+#[doc(hidden)]
+pub const fn encode_utf16<const N: usize>(text: &str) -> [WCHAR; N] {
+    let bytes = text.as_bytes();
+    let mut units = [0; N];
+    let mut i = 0;
+    let mut j = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i] as u32;
+        let (c, width) = if b < 0x80 {
+            (b, 1)
+        } else if b < 0xE0 {
+            (((b & 0x1F) << 6) | (bytes[i + 1] as u32 & 0x3F), 2)
+        } else if b < 0xF0 {
+            (
+                ((b & 0x0F) << 12)
+                    | ((bytes[i + 1] as u32 & 0x3F) << 6)
+                    | (bytes[i + 2] as u32 & 0x3F),
+                3,
+            )
+        } else {
+            (
+                ((b & 0x07) << 18)
+                    | ((bytes[i + 1] as u32 & 0x3F) << 12)
+                    | ((bytes[i + 2] as u32 & 0x3F) << 6)
+                    | (bytes[i + 3] as u32 & 0x3F),
+                4,
+            )
+        };
+
+        if c == 0 {
+            panic!("constant_text! can't contain NUL characters");
+        }
+
+        if c >= 0x10000 {
+            let c = c - 0x10000;
+            units[j] = (0xD800 | (c >> 10)) as WCHAR;
+            units[j + 1] = (0xDC00 | (c & 0x3FF)) as WCHAR;
+            j += 2;
+        } else {
+            units[j] = c as WCHAR;
+            j += 1;
+        }
+
+        i += width;
+    }
+
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // stands in for a Win32 function that takes a NUL-terminated string
+    fn terminated_len(text: &WStr) -> usize {
+        assert_eq!(text.as_slice_with_nul().last(), Some(&0));
+        text.len()
+    }
+
+    #[test]
+    fn constant_text_is_terminated() {
+        assert_eq!(terminated_len(constant_text!("DISPLAY")), 7);
+        assert_eq!(terminated_len(constant_text!("")), 0);
+    }
+
+    #[test]
+    fn constant_text_matches_encode_utf16() {
+        let text = constant_text!("héllo\n€");
+        let expected: Vec<WCHAR> = "héllo\n€".encode_utf16().chain(Some(0)).collect();
+        assert_eq!(text.as_slice_with_nul(), &expected[..]);
+    }
+
+    #[test]
+    fn constant_text_encodes_surrogate_pairs() {
+        assert_eq!(utf16_len("😀"), 2);
+        assert_eq!(encode_utf16::<3>("😀"), [0xD83D, 0xDE00, 0]);
+        assert_eq!(
+            constant_text!("😀").as_slice_with_nul(),
+            &[0xD83D, 0xDE00, 0]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn encode_utf16_rejects_interior_nul() {
+        encode_utf16::<4>("a\0b");
+    }
+}